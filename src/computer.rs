@@ -1,23 +1,82 @@
 use crate::read_to_string;
+use std::collections::VecDeque;
 use std::ops::{Index, IndexMut};
 
+pub mod amplifier;
+pub mod analysis;
+pub mod ascii;
+pub mod assembler;
+pub mod debugger;
+mod disassembler;
+mod error;
+pub mod history;
+pub mod io;
+pub mod limits;
+pub mod memory;
+pub mod network;
+pub mod profile;
+pub mod replay;
+pub mod runtime;
+pub mod search;
+pub mod snapshot;
+pub mod symbolic;
+pub mod trace;
+
+pub use self::error::IntCodeError;
+use self::history::History;
+use self::io::{InputSource, OutputSink, Port};
+use self::limits::Budget;
+use self::memory::{Memory, SparseMemory, Storage};
+use self::profile::Profile;
+use self::trace::TraceEntry;
+
+/// Instructions beyond this address are decoded on every visit rather than cached.
+const MAX_DECODED: usize = 1 << 20;
+
 #[derive(Clone)]
 pub struct IntCodeComputer {
     idx: usize,
-    input: i64,
-    data: Vec<i64>,
+    relative_base: i64,
+    halted: bool,
+    arithmetic: Arithmetic,
+    input: VecDeque<i64>,
+    data: Storage,
+    decoded: Vec<Option<Op>>,
+    trace: Option<Vec<TraceEntry>>,
+    profile: Option<Profile>,
+    budget: Option<Budget>,
+    history: Option<History>,
+    source: Port<dyn InputSource + Send>,
+    sink: Port<dyn OutputSink + Send>,
 }
 
 impl IntCodeComputer {
-    pub fn new(data: Vec<i64>, input: i64) -> Self {
+    pub fn new<I: IntoIterator<Item=i64>>(data: Vec<i64>, input: I) -> Self {
         Self {
             idx: 0,
-            data,
-            input,
+            relative_base: 0,
+            halted: false,
+            arithmetic: Arithmetic::default(),
+            data: Storage::Dense(data),
+            decoded: Vec::new(),
+            input: input.into_iter().collect(),
+            trace: None,
+            profile: None,
+            budget: None,
+            history: None,
+            source: Port::default(),
+            sink: Port::default(),
         }
     }
 
-    pub fn from_input_file(name: &str, input: i64) -> Self {
+    /// Creates a computer backed by sparse memory, for programs that address words far apart.
+    pub fn new_sparse<I: IntoIterator<Item=i64>>(data: Vec<i64>, input: I) -> Self {
+        let mut comp = Self::new(Vec::new(), input);
+        comp.data = Storage::Sparse(SparseMemory::from(data));
+        comp
+    }
+
+    pub fn from_input_file<I: IntoIterator<Item=i64>>(name: &str, input: I) -> Self {
         let data = read_to_string(name)
             .split(',')
             .map(|a| a.parse::<i64>())
@@ -29,103 +88,311 @@ impl IntCodeComputer {
 
     pub fn run(&mut self) { self.last(); }
 
+    /// Runs until the program produces an output, blocks on an empty input queue, or halts.
+    /// A blocked computer resumes from the same `Input` instruction once input is pushed.
+    /// Outputs delivered to an attached sink do not pause the computer.
+    pub fn resume(&mut self) -> Result<State, IntCodeError> {
+        while !self.halted {
+            match self.try_step() {
+                Ok(Some(output)) if !self.sink.is_attached() => return Ok(State::Output(output)),
+                Ok(_) => {}
+                Err(IntCodeError::InputExhausted { .. }) => return Ok(State::NeedsInput),
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(State::Halted)
+    }
+
+    pub fn resume_with(&mut self, input: i64) -> Result<State, IntCodeError> {
+        self.push_input(input);
+        self.resume()
+    }
+
+    /// Runs until the program halts, collecting every output along the way.
+    pub fn try_run(&mut self) -> Result<Vec<i64>, IntCodeError> {
+        let mut outputs = Vec::new();
+        while !self.halted {
+            if let Some(output) = self.try_step()? {
+                outputs.push(output);
+            }
+        }
+        Ok(outputs)
+    }
+
+    /// Executes a single instruction, returning its output if it produced one. Stepping a
+    /// halted computer is a no-op. On error the instruction pointer is left on the faulting
+    /// instruction.
+    pub fn try_step(&mut self) -> Result<Option<i64>, IntCodeError> {
+        if self.halted {
+            return Ok(None);
+        }
+        if let Some(budget) = &self.budget {
+            budget.check(self.idx)?;
+        }
+        if self.source.is_attached() && self.awaiting_input() {
+            self.pull_input();
+        }
+
+        let idx = self.idx;
+        let op = if self.profile.is_some() { Op::decode(self.read(idx), idx).ok() } else { None };
+        let entry = if self.trace.is_some() { self.begin_trace() } else { None };
+        let change = if self.history.is_some() { Some(self.begin_change()) } else { None };
+        let result = self.execute();
+
+        if let Ok(output) = result {
+            if let Some(entry) = entry {
+                self.end_trace(entry, output);
+            }
+            if let Some(change) = change {
+                self.end_change(change, output);
+            }
+            if let (Some(profile), Some(op)) = (self.profile.as_mut(), op) {
+                profile.record(idx, op, self.idx);
+            }
+            if let Some(budget) = self.budget.as_mut() {
+                budget.record(output.is_some());
+            }
+            if let Some(value) = output {
+                self.push_output(value);
+            }
+        }
+        result
+    }
+
+    fn execute(&mut self) -> Result<Option<i64>, IntCodeError> {
+        use Op::*;
+
+        if self.halted {
+            return Ok(None);
+        }
+
+        if self.idx >= self.len() {
+            return Err(IntCodeError::OutOfBounds { idx: self.idx });
+        }
+
+        let op = self.decode()?;
+
+        match op {
+            Add(l, r, t) => {
+                let value = self.arithmetic.add(self.param(1, l)?, self.param(2, r)?)
+                    .ok_or(IntCodeError::Overflow { idx: self.idx })?;
+                let target = self.target(3, t)?;
+                self.write(target, value);
+            }
+            Multiply(l, r, t) => {
+                let value = self.arithmetic.mul(self.param(1, l)?, self.param(2, r)?)
+                    .ok_or(IntCodeError::Overflow { idx: self.idx })?;
+                let target = self.target(3, t)?;
+                self.write(target, value);
+            }
+            Input(t) => {
+                let target = self.target(1, t)?;
+                let value = self.input.pop_front()
+                    .ok_or(IntCodeError::InputExhausted { idx: self.idx })?;
+                self.write(target, value);
+            }
+            Output(mode) => {
+                let output = self.param(1, mode)?;
+                self.idx += op.len();
+                return Ok(Some(output));
+            }
+            JumpIfTrue(l, r) => {
+                if self.param(1, l)? != 0 {
+                    let target = self.address(self.param(2, r)?)?;
+                    return self.jump(target).map(|_| None);
+                }
+            }
+            JumpIfFalse(l, r) => {
+                if self.param(1, l)? == 0 {
+                    let target = self.address(self.param(2, r)?)?;
+                    return self.jump(target).map(|_| None);
+                }
+            }
+            LessThan(l, r, t) => {
+                let value = if self.param(1, l)? < self.param(2, r)? { 1 } else { 0 };
+                let target = self.target(3, t)?;
+                self.write(target, value);
+            }
+            Equals(l, r, t) => {
+                let value = if self.param(1, l)? == self.param(2, r)? { 1 } else { 0 };
+                let target = self.target(3, t)?;
+                self.write(target, value);
+            }
+            AdjustRelativeBase(mode) => {
+                self.relative_base = self.arithmetic.add(self.relative_base, self.param(1, mode)?)
+                    .ok_or(IntCodeError::Overflow { idx: self.idx })?;
+            }
+            Halt => {
+                self.halted = true;
+                return Ok(None);
+            }
+        }
+
+        self.idx += op.len();
+        Ok(None)
+    }
+
+    /// Chooses how `Add`, `Multiply` and relative base adjustments handle overflow. The default
+    /// is `Arithmetic::Checked`, which stops with `IntCodeError::Overflow`.
+    pub fn set_arithmetic(&mut self, arithmetic: Arithmetic) { self.arithmetic = arithmetic; }
+
+    pub fn push_input(&mut self, value: i64) { self.input.push_back(value); }
+
+    pub fn is_halted(&self) -> bool { self.halted }
+
+    /// Reports whether the computer is paused on an `Input` instruction with an empty queue.
+    /// Pushing more input and iterating again resumes from that instruction.
+    pub fn awaiting_input(&self) -> bool {
+        !self.halted && self.input.is_empty() && matches!(Op::decode(self.read(self.idx), self.idx), Ok(Op::Input(_)))
+    }
+
     fn len(&self) -> usize { self.data.len() }
+
+    fn param(&self, offset: usize, mode: Mode) -> Result<i64, IntCodeError> {
+        let value = self.read(self.idx + offset);
+        match mode {
+            Mode::Position => Ok(self.read(self.address(value)?)),
+            Mode::Immediate => Ok(value),
+            Mode::Relative => Ok(self.read(self.relative_address(value)?)),
+        }
+    }
+
+    fn target(&self, offset: usize, mode: Mode) -> Result<usize, IntCodeError> {
+        let value = self.read(self.idx + offset);
+        match mode {
+            Mode::Position => self.address(value),
+            Mode::Relative => self.relative_address(value),
+            Mode::Immediate => Err(IntCodeError::ImmediateWrite { idx: self.idx }),
+        }
+    }
+
+    fn jump(&mut self, target: usize) -> Result<(), IntCodeError> {
+        if let Some(budget) = self.budget.as_mut() {
+            budget.jumped(self.idx, target, self.relative_base)?;
+        }
+        self.idx = target;
+        Ok(())
+    }
+
+    fn relative_address(&self, value: i64) -> Result<usize, IntCodeError> {
+        let address = self.relative_base.checked_add(value)
+            .ok_or(IntCodeError::Overflow { idx: self.idx })?;
+        self.address(address)
+    }
+
+    fn address(&self, value: i64) -> Result<usize, IntCodeError> {
+        if value < 0 {
+            return Err(IntCodeError::NegativeAddress { idx: self.idx, address: value });
+        }
+        Ok(value as usize)
+    }
+
+    /// Decodes the instruction at `idx`, reusing the result of an earlier decode at the same
+    /// address. Every write goes through `IndexMut`, which drops the cached entry so
+    /// self-modifying code is decoded afresh.
+    fn decode(&mut self) -> Result<Op, IntCodeError> {
+        if let Some(&Some(op)) = self.decoded.get(self.idx) {
+            return Ok(op);
+        }
+
+        let op = Op::decode(self.read(self.idx), self.idx)?;
+        if self.idx >= MAX_DECODED {
+            return Ok(op);
+        }
+        if self.idx >= self.decoded.len() {
+            self.decoded.resize(self.idx + 1, None);
+        }
+        self.decoded[self.idx] = Some(op);
+        Ok(op)
+    }
+
+    /// Resolves the address the instruction at `idx` will write to, if it writes at all.
+    fn write_target(&self) -> Option<usize> {
+        let op = Op::decode(self.read(self.idx), self.idx).ok()?;
+        if !op.writes() {
+            return None;
+        }
+        let modes = op.modes();
+        self.target(modes.len(), modes[modes.len() - 1]).ok()
+    }
+
+    fn read(&self, address: usize) -> i64 { *self.data.get(address) }
+
+    fn write(&mut self, address: usize, value: i64) {
+        let old = self.read(address);
+        if let Some(history) = self.history.as_mut() {
+            history.record_write(address, old);
+        }
+        self[address] = value;
+    }
 }
 
 impl Iterator for IntCodeComputer {
     type Item = i64;
 
     fn next(&mut self) -> Option<Self::Item> {
-        use Op::*;
-        use Mode::*;
+        match self.resume() {
+            Ok(State::Output(output)) => Some(output),
+            Ok(State::NeedsInput) | Ok(State::Halted) => None,
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
 
-        while self.idx < self.len() {
-            let idx = self.idx;
-            let data = &mut self.data;
-            let op = Op::from(data[idx]);
-
-            match op {
-                Add(l, r, Position) => {
-                    let (lhs, rhs, target) = (data[idx + 1], data[idx + 2], data[idx + 3]);
-                    let left = if l == Position { data[lhs as usize] } else { lhs };
-                    let right = if r == Position { data[rhs as usize] } else { rhs };
-                    data[target as usize] = left + right;
-                }
-                Multiply(l, r, Position) => {
-                    let (lhs, rhs, target) = (data[idx + 1], data[idx + 2], data[idx + 3]);
-                    let left = if l == Position { data[lhs as usize] } else { lhs };
-                    let right = if r == Position { data[rhs as usize] } else { rhs };
-                    data[target as usize] = left * right;
-                }
-                Input(Position) => {
-                    let address = data[idx + 1] as usize;
-                    data[address] = self.input;
-                }
-                Output(mode) => {
-                    let value = data[idx + 1];
-                    let output = if mode == Position { data[value as usize] } else { value };
-                    self.idx += op.len();
-                    return Some(output);
-                }
-                JumpIfTrue(l, r) => {
-                    let (first, second) = (data[idx + 1], data[idx + 2]);
-                    let check = if l == Position { data[first as usize] } else { first };
-
-                    if check == 0 {
-                        self.idx += 3;
-                    } else {
-                        let pos = if r == Position { data[second as usize] } else { second };
-                        self.idx = pos as usize
-                    }
-                }
-                JumpIfFalse(l, r) => {
-                    let (first, second) = (data[idx + 1], data[idx + 2]);
-                    let check = if l == Position { data[first as usize] } else { first };
-
-                    if check != 0 {
-                        self.idx += 3;
-                    } else {
-                        let pos = if r == Position { data[second as usize] } else { second };
-                        self.idx = pos as usize
-                    }
-                }
-                LessThan(l, r, Position) => {
-                    let (lhs, rhs, target) = (data[idx + 1], data[idx + 2], data[idx + 3]);
-                    let left = if l == Position { data[lhs as usize] } else { lhs };
-                    let right = if r == Position { data[rhs as usize] } else { rhs };
-                    data[target as usize] = if left < right { 1 } else { 0 }
-                }
-                Equals(l, r, Position) => {
-                    let (lhs, rhs, target) = (data[idx + 1], data[idx + 2], data[idx + 3]);
-                    let left = if l == Position { data[lhs as usize] } else { lhs };
-                    let right = if r == Position { data[rhs as usize] } else { rhs };
-                    data[target as usize] = if left == right { 1 } else { 0 }
-                }
-                Halt => {
-                    self.idx = self.len();
-                    return None;
-                }
-                _ => panic!("invalid operation at idx {}: {:?}", idx, op),
-            }
-            self.idx += op.len();
+#[derive(PartialEq, Copy, Clone, Debug, Default)]
+pub enum Arithmetic {
+    #[default]
+    Checked,
+    Wrapping,
+    Saturating,
+}
+
+impl Arithmetic {
+    fn add(self, lhs: i64, rhs: i64) -> Option<i64> {
+        match self {
+            Arithmetic::Checked => lhs.checked_add(rhs),
+            Arithmetic::Wrapping => Some(lhs.wrapping_add(rhs)),
+            Arithmetic::Saturating => Some(lhs.saturating_add(rhs)),
         }
+    }
 
-        None
+    fn mul(self, lhs: i64, rhs: i64) -> Option<i64> {
+        match self {
+            Arithmetic::Checked => lhs.checked_mul(rhs),
+            Arithmetic::Wrapping => Some(lhs.wrapping_mul(rhs)),
+            Arithmetic::Saturating => Some(lhs.saturating_mul(rhs)),
+        }
     }
 }
 
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum State {
+    NeedsInput,
+    Output(i64),
+    Halted,
+}
+
 impl Index<usize> for IntCodeComputer {
     type Output = i64;
 
-    fn index(&self, index: usize) -> &Self::Output { &self.data[index] }
+    fn index(&self, index: usize) -> &Self::Output { self.data.get(index) }
 }
 
 impl IndexMut<usize> for IntCodeComputer {
-    fn index_mut(&mut self, index: usize) -> &mut Self::Output { &mut self.data[index] }
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        if let Some(op) = self.decoded.get_mut(index) {
+            *op = None;
+        }
+        if let Some(budget) = self.budget.as_mut() {
+            budget.progressed();
+        }
+        self.data.get_mut(index)
+    }
 }
 
 #[derive(PartialEq, Copy, Clone, Debug)]
-enum Op {
+pub enum Op {
     Add(Mode, Mode, Mode),
     Multiply(Mode, Mode, Mode),
     Input(Mode),
@@ -135,6 +402,7 @@ enum Op {
     JumpIfFalse(Mode, Mode),
     LessThan(Mode, Mode, Mode),
     Equals(Mode, Mode, Mode),
+    AdjustRelativeBase(Mode),
 }
 
 impl Op {
@@ -146,51 +414,246 @@ impl Op {
             Input(_) => 2,
             Output(_) => 2,
             Halt => 1,
-            JumpIfTrue(_, _) => 0, // manually jump
-            JumpIfFalse(_, _) => 0, // manually jump
+            JumpIfTrue(_, _) => 3,
+            JumpIfFalse(_, _) => 3,
             LessThan(_, _, _) => 4,
             Equals(_, _, _) => 4,
+            AdjustRelativeBase(_) => 2,
         }
     }
-}
 
-impl From<i64> for Op {
-    fn from(c: i64) -> Self {
+    fn mnemonic(self) -> &'static str {
         use Op::*;
-        match c % 100 {
-            1 => Add(Mode::first(c), Mode::second(c), Mode::third(c)),
-            2 => Multiply(Mode::first(c), Mode::second(c), Mode::third(c)),
-            3 => Input(Mode::first(c)),
-            4 => Output(Mode::first(c)),
-            5 => JumpIfTrue(Mode::first(c), Mode::second(c)),
-            6 => JumpIfFalse(Mode::first(c), Mode::second(c)),
-            7 => LessThan(Mode::first(c), Mode::second(c), Mode::third(c)),
-            8 => Equals(Mode::first(c), Mode::second(c), Mode::third(c)),
-            99 => Halt,
-            _ => panic!("failed to parse operation: {}", c),
+        match self {
+            Add(_, _, _) => "ADD",
+            Multiply(_, _, _) => "MUL",
+            Input(_) => "IN",
+            Output(_) => "OUT",
+            Halt => "HLT",
+            JumpIfTrue(_, _) => "JT",
+            JumpIfFalse(_, _) => "JF",
+            LessThan(_, _, _) => "LT",
+            Equals(_, _, _) => "EQ",
+            AdjustRelativeBase(_) => "ARB",
         }
     }
+
+    fn modes(self) -> Vec<Mode> {
+        use Op::*;
+        match self {
+            Add(a, b, c) | Multiply(a, b, c) | LessThan(a, b, c) | Equals(a, b, c) => vec![a, b, c],
+            JumpIfTrue(a, b) | JumpIfFalse(a, b) => vec![a, b],
+            Input(a) | Output(a) | AdjustRelativeBase(a) => vec![a],
+            Halt => vec![],
+        }
+    }
+
+    /// Reports whether the final parameter is a write target rather than a value.
+    fn writes(self) -> bool {
+        use Op::*;
+        match self {
+            Add(_, _, _) | Multiply(_, _, _) | LessThan(_, _, _) | Equals(_, _, _) | Input(_) => true,
+            Output(_) | JumpIfTrue(_, _) | JumpIfFalse(_, _) | AdjustRelativeBase(_) | Halt => false,
+        }
+    }
+
+    fn decode(code: i64, idx: usize) -> Result<Self, IntCodeError> {
+        use Op::*;
+        let op = match code % 100 {
+            1 => Add(Mode::first(code, idx)?, Mode::second(code, idx)?, Mode::third(code, idx)?),
+            2 => Multiply(Mode::first(code, idx)?, Mode::second(code, idx)?, Mode::third(code, idx)?),
+            3 => Input(Mode::first(code, idx)?),
+            4 => Output(Mode::first(code, idx)?),
+            5 => JumpIfTrue(Mode::first(code, idx)?, Mode::second(code, idx)?),
+            6 => JumpIfFalse(Mode::first(code, idx)?, Mode::second(code, idx)?),
+            7 => LessThan(Mode::first(code, idx)?, Mode::second(code, idx)?, Mode::third(code, idx)?),
+            8 => Equals(Mode::first(code, idx)?, Mode::second(code, idx)?, Mode::third(code, idx)?),
+            9 => AdjustRelativeBase(Mode::first(code, idx)?),
+            99 => Halt,
+            _ => return Err(IntCodeError::UnknownOpcode { idx, opcode: code }),
+        };
+        Ok(op)
+    }
 }
 
 #[derive(PartialEq, Copy, Clone, Debug)]
-enum Mode {
+pub enum Mode {
     Position,
     Immediate,
+    Relative,
 }
 
 impl Mode {
-    fn first(op: i64) -> Self { (op % 1_000 / 100).into() }
-    fn second(op: i64) -> Self { (op % 10_000/ 1_000).into() }
-    fn third(op: i64) -> Self { (op % 100_000/ 10_000).into() }
-}
+    fn first(op: i64, idx: usize) -> Result<Self, IntCodeError> { Mode::decode(op % 1_000 / 100, op, idx) }
+    fn second(op: i64, idx: usize) -> Result<Self, IntCodeError> { Mode::decode(op % 10_000 / 1_000, op, idx) }
+    fn third(op: i64, idx: usize) -> Result<Self, IntCodeError> { Mode::decode(op % 100_000 / 10_000, op, idx) }
 
-impl From<i64> for Mode {
-    fn from(i: i64) -> Self {
+    fn decode(digit: i64, opcode: i64, idx: usize) -> Result<Self, IntCodeError> {
         use Mode::*;
-        match i {
-            0 => Position,
-            1 => Immediate,
-            _ => unreachable!(),
+        match digit {
+            0 => Ok(Position),
+            1 => Ok(Immediate),
+            2 => Ok(Relative),
+            _ => Err(IntCodeError::InvalidMode { idx, opcode }),
         }
     }
-}
\ No newline at end of file
+}
+
+/// Counts [12] down from 3, outputting each value. Shared by tests across the computer modules.
+#[cfg(test)]
+fn countdown() -> IntCodeComputer {
+    IntCodeComputer::new(vec![4, 12, 1001, 12, -1, 12, 1005, 12, 0, 99, 0, 0, 3], None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::assembler::assemble;
+
+    #[test]
+    fn test_input_queue() {
+        let mut comp = IntCodeComputer::new(vec![3, 11, 3, 12, 1, 11, 12, 11, 4, 11, 99, 0, 0], vec![3, 4]);
+        assert_eq!(comp.next(), Some(7));
+        assert_eq!(comp.next(), None);
+        assert!(comp.is_halted());
+    }
+
+    #[test]
+    fn test_input_exhausted() {
+        let mut comp = IntCodeComputer::new(vec![3, 11, 3, 12, 1, 11, 12, 11, 4, 11, 99, 0, 0], vec![3]);
+        assert_eq!(comp.next(), None);
+        assert!(comp.awaiting_input());
+        assert!(!comp.is_halted());
+
+        comp.push_input(5);
+        assert!(!comp.awaiting_input());
+        assert_eq!(comp.next(), Some(8));
+    }
+
+    #[test]
+    fn test_relative_mode() {
+        let mut comp = IntCodeComputer::new(vec![109, 7, 204, -2, 21101, 3, 4, -7, 99], None);
+        assert_eq!(comp.next(), Some(3));
+        assert_eq!(comp.next(), None);
+        assert_eq!(comp[0], 7);
+    }
+
+    #[test]
+    fn test_quine() {
+        let program = vec![109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99];
+        let comp = IntCodeComputer::new(program.clone(), None);
+        assert_eq!(comp.collect::<Vec<_>>(), program);
+    }
+
+    #[test]
+    fn test_growing_memory() {
+        let mut comp = IntCodeComputer::new(vec![1101, 2, 3, 1000, 4, 1000, 4, 2000, 99], None);
+        assert_eq!(comp.next(), Some(5));
+        assert_eq!(comp.next(), Some(0));
+        assert_eq!(comp[1000], 5);
+        assert_eq!(comp[5000], 0);
+
+        comp[3000] = 1;
+        assert_eq!(comp[3000], 1);
+    }
+
+    #[test]
+    fn test_sparse_memory() {
+        let program = vec![1101, 2, 3, 5_000_000_000, 4, 5_000_000_000, 99];
+
+        let mut comp = IntCodeComputer::new(program.clone(), None);
+        assert_eq!(comp.next(), Some(5));
+        assert!(matches!(comp.data, Storage::Sparse(_)));
+
+        let mut comp = IntCodeComputer::new_sparse(program, None);
+        assert_eq!(comp.try_run(), Ok(vec![5]));
+        assert_eq!(comp[5_000_000_000], 5);
+        assert_eq!(comp[6], 99);
+    }
+
+    #[test]
+    #[should_panic(expected = "negative address -1")]
+    fn test_negative_address() {
+        IntCodeComputer::new(vec![4, -1, 99], None).run();
+    }
+
+    #[test]
+    fn test_self_modifying() {
+        let program = assemble("
+            top:    OUT #7
+                    JT [flag] #done
+                    ADD #0 #1 -> [flag]
+                    ADD #4 #0 -> [top]      ; turns OUT #7 into OUT [7]
+                    JT #1 #top
+            done:   HLT
+            flag:   .data 0
+        ").unwrap();
+
+        let comp = IntCodeComputer::new(program, None);
+        assert_eq!(comp.collect::<Vec<_>>(), vec![7, 1]);
+    }
+
+    #[test]
+    fn test_errors() {
+        use IntCodeError::*;
+
+        let run = |data: Vec<i64>| IntCodeComputer::new(data, None).try_run();
+
+        assert_eq!(run(vec![1, 0, 0, 0, 42]), Err(UnknownOpcode { idx: 4, opcode: 42 }));
+        assert_eq!(run(vec![304, 0, 99]), Err(InvalidMode { idx: 0, opcode: 304 }));
+        assert_eq!(run(vec![11101, 1, 1, 0, 99]), Err(ImmediateWrite { idx: 0 }));
+        assert_eq!(run(vec![104, 7, 4, -3, 99]), Err(NegativeAddress { idx: 2, address: -3 }));
+        assert_eq!(run(vec![1105, 1, 100]), Err(OutOfBounds { idx: 100 }));
+        assert_eq!(run(vec![3, 0, 99]), Err(InputExhausted { idx: 0 }));
+        assert_eq!(run(vec![104, 7, 99]), Ok(vec![7]));
+    }
+
+    #[test]
+    fn test_resume() {
+        let mut comp = IntCodeComputer::new(vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8], None);
+        assert_eq!(comp.resume(), Ok(State::NeedsInput));
+        assert_eq!(comp.resume(), Ok(State::NeedsInput));
+        assert_eq!(comp.resume_with(8), Ok(State::Output(1)));
+        assert_eq!(comp.resume(), Ok(State::Halted));
+        assert_eq!(comp.resume_with(8), Ok(State::Halted));
+
+        let mut comp = IntCodeComputer::new(vec![1105, 1, 100], None);
+        assert_eq!(comp.resume(), Err(IntCodeError::OutOfBounds { idx: 100 }));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let run = |program: Vec<i64>, arithmetic| {
+            let mut comp = IntCodeComputer::new(program, None);
+            comp.set_arithmetic(arithmetic);
+            comp.try_run()
+        };
+
+        let add = vec![1101, i64::MAX, 2, 0, 4, 0, 99];
+        assert_eq!(run(add.clone(), Arithmetic::Checked), Err(IntCodeError::Overflow { idx: 0 }));
+        assert_eq!(run(add.clone(), Arithmetic::Wrapping), Ok(vec![i64::MIN + 1]));
+        assert_eq!(run(add, Arithmetic::Saturating), Ok(vec![i64::MAX]));
+
+        let mul = vec![1102, i64::MIN, 3, 0, 4, 0, 99];
+        assert_eq!(run(mul.clone(), Arithmetic::Checked), Err(IntCodeError::Overflow { idx: 0 }));
+        assert_eq!(run(mul.clone(), Arithmetic::Wrapping), Ok(vec![i64::MIN]));
+        assert_eq!(run(mul, Arithmetic::Saturating), Ok(vec![i64::MIN]));
+
+        let relative = vec![109, i64::MAX, 204, 1, 99];
+        assert_eq!(run(relative, Arithmetic::Wrapping), Err(IntCodeError::Overflow { idx: 2 }));
+
+        let quine = vec![1102, 34_915_192, 34_915_192, 7, 4, 7, 99, 0];
+        assert_eq!(run(quine, Arithmetic::Checked), Ok(vec![1_219_070_632_396_864]));
+    }
+
+    #[test]
+    fn test_try_step() {
+        let mut comp = IntCodeComputer::new(vec![1101, 2, 3, 7, 4, 7, 99, 0], None);
+        assert_eq!(comp.try_step(), Ok(None));
+        assert_eq!(comp.try_step(), Ok(Some(5)));
+        assert!(!comp.is_halted());
+        assert_eq!(comp.try_step(), Ok(None));
+        assert!(comp.is_halted());
+        assert_eq!(comp.try_step(), Ok(None));
+    }
+}
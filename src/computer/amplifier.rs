@@ -0,0 +1,145 @@
+use super::{IntCodeComputer, IntCodeError, State};
+
+/// Wires copies of a program in series, each machine's output feeding the next one's input.
+/// In feedback mode the last machine also feeds the first until every machine halts.
+pub struct AmplifierChain {
+    program: IntCodeComputer,
+    feedback: bool,
+}
+
+impl AmplifierChain {
+    pub fn series(program: IntCodeComputer) -> Self { Self { program, feedback: false } }
+
+    pub fn feedback(program: IntCodeComputer) -> Self { Self { program, feedback: true } }
+
+    /// Boots one machine per phase setting and passes `signal` into the first. Returns the
+    /// last signal produced by the final machine, or `None` if it never produced one.
+    pub fn run(&self, phases: &[i64], signal: i64) -> Result<Option<i64>, IntCodeError> {
+        let mut amps: Vec<IntCodeComputer> = phases.iter()
+            .map(|&phase| {
+                let mut amp = self.program.clone();
+                amp.push_input(phase);
+                amp
+            })
+            .collect();
+
+        let last = amps.len().saturating_sub(1);
+        let mut signal = Some(signal);
+        let mut thrust = None;
+
+        while !amps.iter().all(IntCodeComputer::is_halted) {
+            for (i, amp) in amps.iter_mut().enumerate() {
+                if let Some(value) = signal.take() {
+                    amp.push_input(value);
+                }
+
+                match amp.resume()? {
+                    State::Output(value) => {
+                        signal = Some(value);
+                        if i == last {
+                            thrust = Some(value);
+                        }
+                    }
+                    State::Halted => {}
+                    State::NeedsInput => return Err(IntCodeError::InputExhausted { idx: amp.idx }),
+                }
+            }
+
+            if !self.feedback {
+                break;
+            }
+        }
+
+        Ok(thrust)
+    }
+
+    /// Tries every ordering of `phases`, returning the one that produces the highest signal.
+    pub fn max_signal(&self, phases: &[i64], signal: i64) -> Result<Option<(Vec<i64>, i64)>, IntCodeError> {
+        let mut best: Option<(Vec<i64>, i64)> = None;
+
+        for order in permutations(phases) {
+            if let Some(thrust) = self.run(&order, signal)? {
+                if best.as_ref().map_or(true, |&(_, max)| thrust > max) {
+                    best = Some((order, thrust));
+                }
+            }
+        }
+
+        Ok(best)
+    }
+}
+
+fn permutations(items: &[i64]) -> Vec<Vec<i64>> {
+    if items.len() <= 1 {
+        return vec![items.to_vec()];
+    }
+
+    let mut out = Vec::new();
+    for i in 0..items.len() {
+        let mut rest = items.to_vec();
+        let head = rest.remove(i);
+        for mut tail in permutations(&rest) {
+            tail.insert(0, head);
+            out.push(tail);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(data: Vec<i64>, feedback: bool) -> AmplifierChain {
+        let program = IntCodeComputer::new(data, None);
+        if feedback { AmplifierChain::feedback(program) } else { AmplifierChain::series(program) }
+    }
+
+    #[test]
+    fn test_permutations() {
+        assert_eq!(permutations(&[0, 1, 2, 3, 4]).len(), 120);
+        assert_eq!(permutations(&[1, 2]), vec![vec![1, 2], vec![2, 1]]);
+    }
+
+    #[test]
+    fn test_series() {
+        let amps = chain(vec![3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0], false);
+        assert_eq!(amps.run(&[4, 3, 2, 1, 0], 0), Ok(Some(43_210)));
+        assert_eq!(amps.max_signal(&[0, 1, 2, 3, 4], 0), Ok(Some((vec![4, 3, 2, 1, 0], 43_210))));
+
+        let amps = chain(vec![
+            3, 23, 3, 24, 1002, 24, 10, 24, 1002, 23, -1, 23, 101, 5, 23, 23, 1, 24, 23, 23, 4, 23,
+            99, 0, 0,
+        ], false);
+        assert_eq!(amps.max_signal(&[0, 1, 2, 3, 4], 0), Ok(Some((vec![0, 1, 2, 3, 4], 54_321))));
+
+        let amps = chain(vec![
+            3, 31, 3, 32, 1002, 32, 10, 32, 1001, 31, -2, 31, 1007, 31, 0, 33, 1002, 33, 7, 33, 1,
+            33, 31, 31, 1, 32, 31, 31, 4, 31, 99, 0, 0, 0,
+        ], false);
+        assert_eq!(amps.max_signal(&[0, 1, 2, 3, 4], 0), Ok(Some((vec![1, 0, 4, 3, 2], 65_210))));
+    }
+
+    #[test]
+    fn test_feedback() {
+        let amps = chain(vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28,
+            1005, 28, 6, 99, 0, 0, 5,
+        ], true);
+        assert_eq!(amps.run(&[9, 8, 7, 6, 5], 0), Ok(Some(139_629_729)));
+        assert_eq!(amps.max_signal(&[5, 6, 7, 8, 9], 0), Ok(Some((vec![9, 8, 7, 6, 5], 139_629_729))));
+
+        let amps = chain(vec![
+            3, 52, 1001, 52, -5, 52, 3, 53, 1, 52, 56, 54, 1007, 54, 5, 55, 1005, 55, 26, 1001, 54,
+            -5, 54, 1105, 1, 12, 1, 53, 54, 53, 1008, 54, 0, 55, 1001, 55, 1, 55, 2, 53, 55, 53, 4,
+            53, 1001, 56, -1, 56, 1005, 56, 6, 99, 0, 0, 0, 0, 10,
+        ], true);
+        assert_eq!(amps.max_signal(&[5, 6, 7, 8, 9], 0), Ok(Some((vec![9, 7, 8, 5, 6], 18_216))));
+    }
+
+    #[test]
+    fn test_starved() {
+        let amps = chain(vec![3, 0, 3, 0, 99], false);
+        assert_eq!(amps.run(&[1, 2], 0), Err(IntCodeError::InputExhausted { idx: 2 }));
+    }
+}
@@ -0,0 +1,365 @@
+use super::{IntCodeComputer, Mode, Op};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+/// A control-flow edge out of a block.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Edge {
+    Jump(usize),
+    FallThrough(usize),
+}
+
+impl Edge {
+    pub fn to(self) -> usize {
+        match self {
+            Edge::Jump(to) | Edge::FallThrough(to) => to,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Instruction {
+    pub idx: usize,
+    pub op: Op,
+    pub text: String,
+}
+
+/// A straight-line run of instructions, entered only at `start` and left only after its last
+/// instruction. `indirect` marks a block ending in a jump whose target is only known at runtime.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Block {
+    pub start: usize,
+    pub end: usize,
+    pub instructions: Vec<Instruction>,
+    pub successors: Vec<Edge>,
+    pub reachable: bool,
+    pub indirect: bool,
+}
+
+/// An instruction whose position-mode target lands inside reachable code, including words that
+/// control reaches but that do not decode until the program patches them.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct CodeWrite {
+    pub idx: usize,
+    pub address: usize,
+}
+
+/// The result of statically analyzing a program's memory.
+///
+/// Code is found by following every path from address 0 through fall-throughs and jumps with
+/// immediate targets. The words left over are swept in order, skipping unset words of sparse
+/// memory, and those that decode as instructions form unreachable blocks. Programs that jump through memory or rewrite
+/// themselves can reach code that looks unreachable here, so treat it as a lead, not a proof.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Analysis {
+    pub blocks: Vec<Block>,
+    pub code_writes: Vec<CodeWrite>,
+}
+
+impl Analysis {
+    pub fn block_at(&self, start: usize) -> Option<&Block> {
+        self.blocks.binary_search_by_key(&start, |b| b.start).ok().map(|i| &self.blocks[i])
+    }
+
+    pub fn unreachable(&self) -> impl Iterator<Item=&Block> { self.blocks.iter().filter(|b| !b.reachable) }
+
+    /// Writes the control-flow graph in Graphviz DOT format. Unreachable blocks are dashed and
+    /// blocks that write into code are red.
+    pub fn write_dot<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let modifying: BTreeSet<usize> = self.code_writes.iter().map(|cw| cw.idx).collect();
+
+        writeln!(w, "digraph intcode {{")?;
+        writeln!(w, "    node [shape=box, fontname=monospace];")?;
+        for block in &self.blocks {
+            let label: String = block.instructions.iter()
+                .map(|i| format!("{:>5}  {}\\l", i.idx, i.text))
+                .collect();
+            let mut attrs = vec![format!("label=\"{}\"", label)];
+            if !block.reachable {
+                attrs.push("style=dashed".to_string());
+            }
+            if block.instructions.iter().any(|i| modifying.contains(&i.idx)) {
+                attrs.push("color=red".to_string());
+            }
+            writeln!(w, "    b{} [{}];", block.start, attrs.join(", "))?;
+        }
+        for block in &self.blocks {
+            for edge in &block.successors {
+                match edge {
+                    Edge::Jump(to) => writeln!(w, "    b{} -> b{} [label=\"jump\"];", block.start, to)?,
+                    Edge::FallThrough(to) => writeln!(w, "    b{} -> b{};", block.start, to)?,
+                }
+            }
+        }
+        writeln!(w, "}}")
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut buf = Vec::new();
+        self.write_dot(&mut buf).expect("writing to a Vec cannot fail");
+        String::from_utf8(buf).expect("DOT output is UTF-8")
+    }
+}
+
+impl IntCodeComputer {
+    /// Splits the program into basic blocks and builds its control-flow graph without running it.
+    pub fn analyze(&self) -> Analysis {
+        let mut reached: BTreeMap<usize, Op> = BTreeMap::new();
+        let mut undecoded = BTreeSet::new();
+        let mut leaders = BTreeSet::new();
+        let mut work = vec![0];
+        leaders.insert(0);
+
+        while let Some(idx) = work.pop() {
+            if reached.contains_key(&idx) {
+                continue;
+            }
+            let op = match self.instruction_at(idx) {
+                Some(op) => op,
+                None => {
+                    undecoded.insert(idx);
+                    continue;
+                }
+            };
+            reached.insert(idx, op);
+
+            let (edges, _) = self.edges(idx, op);
+            for edge in edges {
+                if let Edge::Jump(to) = edge {
+                    leaders.insert(to);
+                }
+                work.push(edge.to());
+            }
+            if ends_block(op) {
+                leaders.insert(idx + op.len());
+            }
+        }
+
+        let code: BTreeSet<usize> = reached.iter().flat_map(|(&idx, op)| idx..idx + op.len()).collect();
+
+        let mut unreached = BTreeMap::new();
+        let mut next = 0;
+        for idx in self.data.addresses() {
+            if idx < next {
+                continue;
+            }
+            if let Some(op) = reached.get(&idx) {
+                next = idx + op.len();
+                continue;
+            }
+            let op = match self.instruction_at(idx) {
+                Some(op) if !(idx..idx + op.len()).any(|a| code.contains(&a)) => op,
+                _ => continue,
+            };
+
+            let (edges, _) = self.edges(idx, op);
+            leaders.extend(edges.iter().filter_map(|e| match e {
+                Edge::Jump(to) => Some(*to),
+                _ => None,
+            }));
+            if ends_block(op) {
+                leaders.insert(idx + op.len());
+            }
+            unreached.insert(idx, op);
+            next = idx + op.len();
+        }
+
+        let mut blocks: Vec<Block> = Vec::new();
+        let all = reached.iter().map(|(&idx, &op)| (idx, (op, true)))
+            .chain(unreached.iter().map(|(&idx, &op)| (idx, (op, false))))
+            .collect::<BTreeMap<_, _>>();
+
+        for (&idx, &(op, reachable)) in &all {
+            let continues = blocks.last().is_some_and(|b| {
+                let last = b.instructions[b.instructions.len() - 1].op;
+                b.end == idx && b.reachable == reachable && !ends_block(last) && !leaders.contains(&idx)
+            });
+            if !continues {
+                blocks.push(Block {
+                    start: idx,
+                    end: idx,
+                    instructions: Vec::new(),
+                    successors: Vec::new(),
+                    reachable,
+                    indirect: false,
+                });
+            }
+
+            let block = blocks.last_mut().unwrap();
+            block.instructions.push(Instruction { idx, op, text: self.disassemble_at(idx).0 });
+            block.end = idx + op.len();
+        }
+
+        let starts: BTreeSet<usize> = blocks.iter().map(|b| b.start).collect();
+        for block in &mut blocks {
+            let last = &block.instructions[block.instructions.len() - 1];
+            let (edges, indirect) = self.edges(last.idx, last.op);
+            block.successors = edges.into_iter().filter(|e| starts.contains(&e.to())).collect();
+            block.indirect = indirect;
+        }
+
+        let code_writes = reached.iter()
+            .filter_map(|(&idx, &op)| {
+                let modes = op.modes();
+                if !op.writes() || modes[modes.len() - 1] != Mode::Position {
+                    return None;
+                }
+                let address = self.address(self.read(idx + modes.len())).ok()?;
+                if code.contains(&address) || undecoded.contains(&address) {
+                    Some(CodeWrite { idx, address })
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        Analysis { blocks, code_writes }
+    }
+
+    /// Decodes the complete instruction at `idx`, if there is one.
+    fn instruction_at(&self, idx: usize) -> Option<Op> {
+        Op::decode(self.read(idx), idx).ok().filter(|op| idx + op.len() <= self.len())
+    }
+
+    /// Lists where control can go after the instruction at `idx`, and whether it may also jump
+    /// somewhere only known at runtime. Conditions given as immediates are resolved statically.
+    fn edges(&self, idx: usize, op: Op) -> (Vec<Edge>, bool) {
+        let (cond, target, jump_if) = match op {
+            Op::Halt => return (Vec::new(), false),
+            Op::JumpIfTrue(cond, target) => (cond, target, true),
+            Op::JumpIfFalse(cond, target) => (cond, target, false),
+            _ => return (vec![Edge::FallThrough(idx + op.len())], false),
+        };
+
+        let taken = match cond {
+            Mode::Immediate => Some((self.read(idx + 1) != 0) == jump_if),
+            _ => None,
+        };
+
+        let mut edges = Vec::new();
+        let mut indirect = false;
+        if taken != Some(false) {
+            match target {
+                Mode::Immediate if self.read(idx + 2) >= 0 => edges.push(Edge::Jump(self.read(idx + 2) as usize)),
+                Mode::Immediate => {}
+                _ => indirect = true,
+            }
+        }
+        if taken != Some(true) {
+            edges.push(Edge::FallThrough(idx + op.len()));
+        }
+        (edges, indirect)
+    }
+}
+
+fn ends_block(op: Op) -> bool { matches!(op, Op::Halt | Op::JumpIfTrue(_, _) | Op::JumpIfFalse(_, _)) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::assembler::assemble;
+
+    fn computer(source: &str) -> IntCodeComputer { IntCodeComputer::new(assemble(source).unwrap(), None) }
+
+    fn shape(analysis: &Analysis) -> Vec<(usize, usize, bool, Vec<Edge>)> {
+        analysis.blocks.iter().map(|b| (b.start, b.end, b.reachable, b.successors.clone())).collect()
+    }
+
+    #[test]
+    fn test_blocks() {
+        let comp = computer("
+                    IN -> [n]
+            loop:   OUT [n]
+                    ADD [n] #-1 -> [n]
+                    JT [n] #loop
+                    HLT
+            dead:   OUT #1
+                    JT #1 #dead
+            n:      .data 0
+        ");
+
+        use Edge::*;
+        let analysis = comp.analyze();
+        assert_eq!(shape(&analysis), vec![
+            (0, 2, true, vec![FallThrough(2)]),
+            (2, 11, true, vec![Jump(2), FallThrough(11)]),
+            (11, 12, true, vec![]),
+            (12, 17, false, vec![Jump(12)]),
+        ]);
+        assert_eq!(analysis.unreachable().map(|b| b.start).collect::<Vec<_>>(), vec![12]);
+        assert_eq!(analysis.block_at(2).map(|b| b.instructions.len()), Some(3));
+        assert_eq!(analysis.block_at(2).unwrap().instructions[1].text, "ADD [17] #-1 -> [17]");
+        assert!(analysis.code_writes.is_empty());
+    }
+
+    #[test]
+    fn test_indirect_and_self_modifying() {
+        let comp = computer("
+                    ADD #5 #0 -> [patch+1]
+            patch:  OUT #0
+                    JF #0 [target]
+                    HLT
+            target: .data 0
+        ");
+
+        let analysis = comp.analyze();
+        assert_eq!(analysis.code_writes, vec![CodeWrite { idx: 0, address: 5 }]);
+        let block = analysis.block_at(0).unwrap();
+        assert_eq!((block.end, block.indirect, block.successors.clone()), (9, true, vec![]));
+        assert_eq!(analysis.blocks.len(), 2);
+        assert!(!analysis.blocks[1].reachable);
+    }
+
+    #[test]
+    fn test_dot() {
+        let comp = computer("
+                    JT #1 #end
+                    OUT #7
+            end:    HLT
+        ");
+
+        assert_eq!(comp.analyze().to_dot(), "\
+digraph intcode {
+    node [shape=box, fontname=monospace];
+    b0 [label=\"    0  JT #1 #5\\l\"];
+    b3 [label=\"    3  OUT #7\\l\", style=dashed];
+    b5 [label=\"    5  HLT\\l\"];
+    b0 -> b5 [label=\"jump\"];
+    b3 -> b5;
+}
+");
+    }
+
+    #[test]
+    fn test_diagnostic_program() {
+        // The diagnostic program adds its input to the opcode at 6 before running it.
+        let comp = IntCodeComputer::from_input_file("dec05.txt", None);
+        let analysis = comp.analyze();
+
+        let entry = &analysis.blocks[0];
+        assert_eq!((entry.start, entry.end, entry.reachable), (0, 6, true));
+        assert!(entry.successors.is_empty());
+        assert_eq!(analysis.code_writes, vec![CodeWrite { idx: 2, address: 6 }]);
+        assert!(analysis.unreachable().any(|b| b.instructions.iter().any(|i| i.op == Op::Halt)));
+        for block in &analysis.blocks {
+            assert!(block.successors.iter().all(|e| analysis.block_at(e.to()).is_some()));
+        }
+        assert!(!analysis.to_dot().is_empty());
+    }
+
+    #[test]
+    fn test_sparse() {
+        let program = assemble("
+                    OUT [5000000000]
+                    HLT
+                    OUT #9
+        ").unwrap();
+        let mut comp = IntCodeComputer::new_sparse(program, None);
+        comp[5_000_000_000] = 1101;
+
+        let analysis = comp.analyze();
+        assert_eq!(analysis.blocks.iter().map(|b| (b.start, b.reachable)).collect::<Vec<_>>(),
+                   vec![(0, true), (3, false)]);
+        assert_eq!(comp.disassemble(), "    0  OUT [5000000000]\n    2  HLT\n    3  OUT #9\n5000000000  DATA 1101\n");
+    }
+}
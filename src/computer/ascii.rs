@@ -0,0 +1,145 @@
+use super::{IntCodeComputer, IntCodeError, State};
+use std::error::Error;
+use std::fmt;
+
+/// Drives a program that talks in ASCII text: lines are sent as character codes followed by a
+/// newline, and output codes up to 127 are collected as text.
+pub struct AsciiConsole {
+    comp: IntCodeComputer,
+}
+
+/// Everything a program produced before it paused. Output values outside the ASCII range are
+/// collected separately in `values`, in the order they were produced.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Response {
+    pub text: String,
+    pub values: Vec<i64>,
+    pub state: State,
+}
+
+/// A failure while sending a line or running the program.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum AsciiError {
+    Fault(IntCodeError),
+    /// The line held `ch`, which has no ASCII code, at byte offset `index`.
+    NonAscii { index: usize, ch: char },
+}
+
+impl fmt::Display for AsciiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AsciiError::Fault(err) => write!(f, "{}", err),
+            AsciiError::NonAscii { index, ch } => write!(f, "non-ASCII character {:?} at byte {}", ch, index),
+        }
+    }
+}
+
+impl Error for AsciiError {}
+
+impl From<IntCodeError> for AsciiError {
+    fn from(err: IntCodeError) -> Self { AsciiError::Fault(err) }
+}
+
+impl AsciiConsole {
+    pub fn new(comp: IntCodeComputer) -> Self { Self { comp } }
+
+    pub fn computer(&self) -> &IntCodeComputer { &self.comp }
+
+    pub fn into_computer(self) -> IntCodeComputer { self.comp }
+
+    /// Queues `line` followed by a newline without running the program. Nothing is queued if
+    /// the line is not entirely ASCII.
+    pub fn send_line(&mut self, line: &str) -> Result<(), AsciiError> {
+        if let Some((index, ch)) = line.char_indices().find(|(_, c)| !c.is_ascii()) {
+            return Err(AsciiError::NonAscii { index, ch });
+        }
+        for b in line.bytes().chain(Some(b'\n')) {
+            self.comp.push_input(i64::from(b));
+        }
+        Ok(())
+    }
+
+    /// Runs until the program waits for input or halts.
+    pub fn read(&mut self) -> Result<Response, IntCodeError> {
+        let mut text = String::new();
+        let mut values = Vec::new();
+
+        loop {
+            match self.comp.resume()? {
+                State::Output(value @ 0..=127) => text.push(value as u8 as char),
+                State::Output(value) => values.push(value),
+                state => return Ok(Response { text, values, state }),
+            }
+        }
+    }
+
+    /// Sends a line and returns the program's response to it.
+    pub fn command(&mut self, line: &str) -> Result<Response, AsciiError> {
+        self.send_line(line)?;
+        Ok(self.read()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::assembler::assemble;
+
+    // Prompts, then shouts each line back. An empty line reports how many lines were shouted.
+    fn shouter() -> AsciiConsole {
+        let program = assemble("
+                    OUT #62
+                    OUT #10
+            line:   IN -> [c]
+                    EQ [c] #10 -> [t]
+                    JT [t] #done
+            loop:   ADD [c] #-32 -> [c]
+                    OUT [c]
+                    IN -> [c]
+                    EQ [c] #10 -> [t]
+                    JF [t] #loop
+                    OUT #10
+                    ADD [n] #1 -> [n]
+                    JT #1 #line
+            done:   ADD [n] #48 -> [n]
+                    OUT [n]
+                    OUT #1000
+                    HLT
+            c:      .data 0
+            t:      .data 0
+            n:      .data 0
+        ").unwrap();
+        AsciiConsole::new(IntCodeComputer::new(program, None))
+    }
+
+    #[test]
+    fn test_console() {
+        let mut console = shouter();
+        assert_eq!(console.read(), Ok(Response { text: ">\n".to_string(), values: vec![], state: State::NeedsInput }));
+        assert_eq!(console.command("hello"), Ok(Response {
+            text: "HELLO\n".to_string(),
+            values: vec![],
+            state: State::NeedsInput,
+        }));
+
+        console.send_line("abc").unwrap();
+        console.send_line("xyz").unwrap();
+        assert_eq!(console.read().map(|r| r.text), Ok("ABC\nXYZ\n".to_string()));
+
+        assert_eq!(console.command(""), Ok(Response {
+            text: "3".to_string(),
+            values: vec![1000],
+            state: State::Halted,
+        }));
+        assert!(console.computer().is_halted());
+    }
+
+    #[test]
+    fn test_non_ascii() {
+        let mut console = shouter();
+        console.read().unwrap();
+        assert_eq!(console.send_line("caf\u{e9}!"), Err(AsciiError::NonAscii { index: 3, ch: '\u{e9}' }));
+        assert_eq!(console.command("\u{1F600}").unwrap_err().to_string(), "non-ASCII character '\u{1f600}' at byte 0");
+        assert_eq!(console.command("ok").map(|r| r.text), Ok("OK\n".to_string()));
+    }
+}
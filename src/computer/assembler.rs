@@ -0,0 +1,275 @@
+use super::{Mode, Op};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+const OPCODES: [i64; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 99];
+
+/// A problem found while assembling, tagged with the 1-based source line it occurred on.
+#[derive(PartialEq, Clone, Debug)]
+pub struct AssembleError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl AssembleError {
+    fn new(line: usize, message: String) -> Self { Self { line, message } }
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AssembleError {}
+
+/// Assembles source text into a program image for `IntCodeComputer::new`.
+///
+/// Each line holds an optional `label:`, then a mnemonic with its operands or a directive,
+/// then an optional `; comment`. Operands are written `#value` (immediate), `[value]`
+/// (position) or `[rb+value]` (relative), where a value is a number, a label, or a label with
+/// a `+`/`-` offset. A `->` before a write target and commas between operands are optional.
+/// `.data` (or `DATA`) emits its values verbatim and `.zero n` reserves `n` zeroed words.
+///
+/// ```text
+/// loop:   IN -> [x]
+///         JF [x] #done     ; stop on zero
+///         OUT [x]
+///         JT #1 #loop
+/// done:   HLT
+/// x:      .data 0
+/// ```
+pub fn assemble(source: &str) -> Result<Vec<i64>, AssembleError> {
+    let mut labels = HashMap::new();
+    let mut items = Vec::new();
+    let mut address = 0;
+
+    for (i, raw) in source.lines().enumerate() {
+        let line = i + 1;
+        let mut text = raw.split(';').next().unwrap_or("").trim();
+
+        while let Some(colon) = text.find(':') {
+            let label = text[..colon].trim();
+            if !is_label(label) {
+                break;
+            }
+            if labels.insert(label, address).is_some() {
+                return Err(AssembleError::new(line, format!("duplicate label {:?}", label)));
+            }
+            text = text[colon + 1..].trim();
+        }
+
+        let mut tokens = text.split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|t| !t.is_empty() && *t != "->");
+
+        let name = match tokens.next() {
+            Some(name) => name,
+            None => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        let item = Item::parse(line, name, args)?;
+        address += item.len();
+        items.push(item);
+    }
+
+    let mut out = Vec::with_capacity(address);
+    for item in items {
+        item.emit(&labels, &mut out)?;
+    }
+    Ok(out)
+}
+
+enum Item<'a> {
+    Instruction { line: usize, code: i64, operands: Vec<(Mode, &'a str)> },
+    Data { line: usize, values: Vec<&'a str> },
+    Zero(usize),
+}
+
+impl<'a> Item<'a> {
+    fn parse(line: usize, name: &'a str, args: Vec<&'a str>) -> Result<Self, AssembleError> {
+        match name {
+            ".data" | "DATA" => return Ok(Item::Data { line, values: args }),
+            ".zero" => {
+                let count = match args.as_slice() {
+                    [count] => count.parse::<usize>().ok(),
+                    _ => None,
+                };
+                return count
+                    .map(Item::Zero)
+                    .ok_or_else(|| AssembleError::new(line, ".zero takes a single word count".to_string()));
+            }
+            _ => {}
+        }
+
+        let mnemonic = name.to_uppercase();
+        let (code, op) = OPCODES.iter()
+            .filter_map(|&code| Op::decode(code, 0).ok().map(|op| (code, op)))
+            .find(|(_, op)| op.mnemonic() == mnemonic)
+            .ok_or_else(|| AssembleError::new(line, format!("unknown mnemonic {:?}", name)))?;
+
+        let arity = op.modes().len();
+        if args.len() != arity {
+            return Err(AssembleError::new(
+                line, format!("{} takes {} operands, found {}", mnemonic, arity, args.len())));
+        }
+
+        let operands = args.into_iter()
+            .map(|arg| operand(line, arg))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if op.writes() && operands.last().map(|&(mode, _)| mode) == Some(Mode::Immediate) {
+            return Err(AssembleError::new(line, format!("{} cannot write to an immediate", mnemonic)));
+        }
+
+        Ok(Item::Instruction { line, code, operands })
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Item::Instruction { operands, .. } => 1 + operands.len(),
+            Item::Data { values, .. } => values.len(),
+            Item::Zero(count) => *count,
+        }
+    }
+
+    fn emit(self, labels: &HashMap<&str, usize>, out: &mut Vec<i64>) -> Result<(), AssembleError> {
+        match self {
+            Item::Instruction { line, code, operands } => {
+                let mut scale = 100;
+                let mut word = code;
+                for &(mode, _) in &operands {
+                    word += scale * mode_digit(mode);
+                    scale *= 10;
+                }
+                out.push(word);
+
+                for (_, value) in operands {
+                    out.push(resolve(line, value, labels)?);
+                }
+            }
+            Item::Data { line, values } => {
+                for value in values {
+                    out.push(resolve(line, value, labels)?);
+                }
+            }
+            Item::Zero(count) => out.resize(out.len() + count, 0),
+        }
+        Ok(())
+    }
+}
+
+fn operand(line: usize, arg: &str) -> Result<(Mode, &str), AssembleError> {
+    if let Some(value) = arg.strip_prefix('#') {
+        return Ok((Mode::Immediate, value));
+    }
+
+    if let Some(inner) = arg.strip_prefix('[').and_then(|a| a.strip_suffix(']')) {
+        if let Some(offset) = inner.strip_prefix("rb+") {
+            return Ok((Mode::Relative, offset));
+        }
+        if inner.starts_with("rb-") {
+            return Ok((Mode::Relative, &inner[2..]));
+        }
+        return Ok((Mode::Position, inner));
+    }
+
+    Err(AssembleError::new(line, format!("operand {:?} needs a #, [..] or [rb..] mode marker", arg)))
+}
+
+fn resolve(line: usize, value: &str, labels: &HashMap<&str, usize>) -> Result<i64, AssembleError> {
+    if let Ok(number) = value.parse::<i64>() {
+        return Ok(number);
+    }
+
+    let (label, offset) = match value.find(['+', '-']) {
+        Some(split) => {
+            let offset = value[split..].trim_start_matches('+').parse::<i64>()
+                .map_err(|_| AssembleError::new(line, format!("invalid offset in {:?}", value)))?;
+            (&value[..split], offset)
+        }
+        None => (value, 0),
+    };
+
+    labels.get(label)
+        .map(|&address| address as i64 + offset)
+        .ok_or_else(|| AssembleError::new(line, format!("unknown label {:?}", label)))
+}
+
+fn is_label(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        _ => false,
+    }
+}
+
+fn mode_digit(mode: Mode) -> i64 {
+    match mode {
+        Mode::Position => 0,
+        Mode::Immediate => 1,
+        Mode::Relative => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::IntCodeComputer;
+
+    #[test]
+    fn test_assemble() {
+        let program = assemble("
+            ; echo inputs until a zero is read
+            loop:   IN -> [x]
+                    JF [x], #done
+                    OUT [x]
+                    JT #1 #loop
+            done:   HLT
+            x:      .data 0
+        ").unwrap();
+
+        assert_eq!(program, vec![3, 11, 1006, 11, 10, 4, 11, 1105, 1, 0, 99, 0]);
+
+        let comp = IntCodeComputer::new(program, vec![4, 5, 0]);
+        assert_eq!(comp.collect::<Vec<_>>(), vec![4, 5]);
+    }
+
+    #[test]
+    fn test_relative_and_offsets() {
+        let program = assemble("
+            ARB #buf
+            ADD [rb+1] #10 -> [rb-1]
+            MUL #2 [buf+1] -> [out]
+            HLT
+            buf: .data 7 8
+            .zero 2
+            out: DATA -1
+        ").unwrap();
+
+        assert_eq!(program, vec![109, 11, 21201, 1, 10, -1, 102, 2, 12, 15, 99, 7, 8, 0, 0, -1]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let program = vec![1002, 12, 3, 7, 109, -4, 21107, 1, 2, -1, 99, 42, 1105];
+        let comp = IntCodeComputer::new(program.clone(), None);
+        let source: String = comp.disassemble().lines()
+            .map(|l| format!("{}\n", &l[7..]))
+            .collect();
+        assert_eq!(assemble(&source), Ok(program));
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = |src: &str| assemble(src).unwrap_err();
+
+        assert_eq!(err("NOP"), AssembleError::new(1, "unknown mnemonic \"NOP\"".to_string()));
+        assert_eq!(err("ADD #1 #2"), AssembleError::new(1, "ADD takes 3 operands, found 2".to_string()));
+        assert_eq!(err("IN -> #4"), AssembleError::new(1, "IN cannot write to an immediate".to_string()));
+        assert_eq!(err("\nOUT 4"), AssembleError::new(2, "operand \"4\" needs a #, [..] or [rb..] mode marker".to_string()));
+        assert_eq!(err("JT #1 #nowhere"), AssembleError::new(1, "unknown label \"nowhere\"".to_string()));
+        assert_eq!(err("a: HLT\na: HLT"), AssembleError::new(2, "duplicate label \"a\"".to_string()));
+    }
+}
@@ -0,0 +1,445 @@
+use super::history::Change;
+use super::{IntCodeComputer, IntCodeError};
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+
+/// Wraps a computer with single-stepping, breakpoints on instruction addresses and
+/// watchpoints on memory writes. Outputs are collected rather than pausing execution. With
+/// history enabled, execution can also be stepped backward.
+pub struct Debugger {
+    comp: IntCodeComputer,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: BTreeSet<usize>,
+    outputs: Vec<i64>,
+    owns_history: bool,
+}
+
+/// Why the debugger handed control back to the caller.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Stop {
+    Step,
+    Breakpoint(usize),
+    Watchpoint { idx: usize, address: usize, old: i64, new: i64 },
+    NeedsInput,
+    Halted,
+}
+
+impl Debugger {
+    pub fn new(comp: IntCodeComputer) -> Self {
+        Self {
+            comp,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeSet::new(),
+            outputs: Vec::new(),
+            owns_history: false,
+        }
+    }
+
+    /// Creates a debugger that logs the last `limit` instructions so it can step backward. The
+    /// log is turned off again by `into_computer` unless the computer already kept one.
+    pub fn with_history(mut comp: IntCodeComputer, limit: usize) -> Self {
+        let owns_history = comp.history.is_none();
+        if owns_history {
+            comp.enable_history(limit);
+        }
+        Self { owns_history, ..Self::new(comp) }
+    }
+
+    pub fn computer(&self) -> &IntCodeComputer { &self.comp }
+
+    pub fn into_computer(mut self) -> IntCodeComputer {
+        if self.owns_history {
+            self.comp.disable_history();
+        }
+        self.comp
+    }
+
+    pub fn idx(&self) -> usize { self.comp.idx }
+
+    pub fn relative_base(&self) -> i64 { self.comp.relative_base }
+
+    pub fn outputs(&self) -> &[i64] { &self.outputs }
+
+    pub fn peek(&self, address: usize) -> i64 { self.comp[address] }
+
+    pub fn poke(&mut self, address: usize, value: i64) { self.comp[address] = value; }
+
+    pub fn push_input(&mut self, value: i64) { self.comp.push_input(value); }
+
+    pub fn add_breakpoint(&mut self, idx: usize) -> bool { self.breakpoints.insert(idx) }
+
+    pub fn remove_breakpoint(&mut self, idx: usize) -> bool { self.breakpoints.remove(&idx) }
+
+    pub fn add_watchpoint(&mut self, address: usize) -> bool { self.watchpoints.insert(address) }
+
+    pub fn remove_watchpoint(&mut self, address: usize) -> bool { self.watchpoints.remove(&address) }
+
+    /// Executes a single instruction, reporting a watchpoint if it wrote to a watched address.
+    pub fn step(&mut self) -> Result<Stop, IntCodeError> {
+        if self.comp.is_halted() {
+            return Ok(Stop::Halted);
+        }
+
+        let idx = self.comp.idx;
+        let watched = self.comp.write_target()
+            .filter(|address| self.watchpoints.contains(address))
+            .map(|address| (address, self.comp[address]));
+
+        match self.comp.try_step() {
+            Ok(Some(output)) => self.outputs.push(output),
+            Ok(None) => {}
+            Err(IntCodeError::InputExhausted { .. }) => return Ok(Stop::NeedsInput),
+            Err(err) => return Err(err),
+        }
+
+        if let Some((address, old)) = watched {
+            return Ok(Stop::Watchpoint { idx, address, old, new: self.comp[address] });
+        }
+
+        if self.comp.is_halted() {
+            return Ok(Stop::Halted);
+        }
+
+        Ok(Stop::Step)
+    }
+
+    /// Runs until a breakpoint is reached, a watchpoint fires, input is needed or the program
+    /// halts. A breakpoint on the current instruction does not stop the first step.
+    pub fn cont(&mut self) -> Result<Stop, IntCodeError> {
+        loop {
+            match self.step()? {
+                Stop::Step => {}
+                stop => return Ok(stop),
+            }
+
+            if self.breakpoints.contains(&self.comp.idx) {
+                return Ok(Stop::Breakpoint(self.comp.idx));
+            }
+        }
+    }
+
+    /// Undoes the last instruction, dropping the output it produced. Returns the address of the
+    /// undone instruction, or `None` once the history is exhausted.
+    pub fn step_back(&mut self) -> Option<usize> {
+        self.rewind(IntCodeComputer::step_back).map(|change| change.idx)
+    }
+
+    /// Steps backward to the most recent instruction that wrote `address`, stopping before it
+    /// executes. Rewinds as far as the history goes if there is no such instruction.
+    pub fn reverse_to_write(&mut self, address: usize) -> Option<Change> {
+        self.rewind(|comp| comp.reverse_to_write(address))
+    }
+
+    /// Runs a backward movement on the computer, dropping the outputs of every undone instruction.
+    fn rewind<F: FnOnce(&mut IntCodeComputer) -> Option<Change>>(&mut self, f: F) -> Option<Change> {
+        let logged = self.comp.history_outputs();
+        let change = f(&mut self.comp);
+        let undone = logged - self.comp.history_outputs();
+        self.outputs.truncate(self.outputs.len().saturating_sub(undone));
+        change
+    }
+
+    pub fn repl_stdin(&mut self) -> io::Result<()> {
+        let stdin = io::stdin();
+        let stdout = io::stdout();
+        self.repl(stdin.lock(), stdout.lock())
+    }
+
+    /// Reads debugger commands line by line until `quit` or end of input. Enter `help` for the
+    /// list of commands.
+    pub fn repl<R: BufRead, W: Write>(&mut self, input: R, mut out: W) -> io::Result<()> {
+        self.print_current(&mut out)?;
+
+        for line in input.lines() {
+            let line = line?;
+            let mut words = line.split_whitespace();
+            let cmd = match words.next() {
+                Some(cmd) => cmd,
+                None => continue,
+            };
+            let args: Vec<i64> = match words.map(str::parse::<i64>).collect() {
+                Ok(args) => args,
+                Err(err) => {
+                    writeln!(out, "invalid argument: {}", err)?;
+                    continue;
+                }
+            };
+            let arg = |i: usize| args.get(i).copied();
+            let address = |i: usize| arg(i).filter(|&a| a >= 0).map(|a| a as usize);
+
+            match (cmd, args.len()) {
+                ("s", _) | ("step", _) => {
+                    for _ in 0..arg(0).unwrap_or(1).max(1) {
+                        let stop = self.step();
+                        if !self.report(&mut out, stop)? {
+                            break;
+                        }
+                    }
+                }
+                ("rs", _) | ("rstep", _) | ("rc", 1) | ("rcontinue", 1) if self.comp.history.is_none() =>
+                    writeln!(out, "history is off, start the debugger with history to step backward")?,
+                ("rs", _) | ("rstep", _) => {
+                    for _ in 0..arg(0).unwrap_or(1).max(1) {
+                        if self.step_back().is_none() {
+                            writeln!(out, "start of history")?;
+                            break;
+                        }
+                    }
+                    self.print_current(&mut out)?;
+                }
+                ("rc", 1) | ("rcontinue", 1) => match address(0) {
+                    Some(address) => {
+                        match self.reverse_to_write(address) {
+                            Some(Change { write: Some((_, old)), .. }) =>
+                                writeln!(out, "[{}] last written here, was {}", address, old)?,
+                            _ => writeln!(out, "start of history, [{}] not written", address)?,
+                        }
+                        self.print_current(&mut out)?;
+                    }
+                    None => writeln!(out, "invalid address")?,
+                },
+                ("c", 0) | ("continue", 0) => {
+                    let stop = self.cont();
+                    self.report(&mut out, stop)?;
+                }
+                ("b", 1) | ("break", 1) => match address(0) {
+                    Some(idx) => {
+                        self.add_breakpoint(idx);
+                        writeln!(out, "added breakpoint at {}", idx)?;
+                    }
+                    None => writeln!(out, "invalid address")?,
+                },
+                ("d", 1) | ("delete", 1) => match address(0) {
+                    Some(idx) if self.remove_breakpoint(idx) => writeln!(out, "deleted breakpoint at {}", idx)?,
+                    _ => writeln!(out, "no breakpoint there")?,
+                },
+                ("w", 1) | ("watch", 1) => match address(0) {
+                    Some(address) => {
+                        self.add_watchpoint(address);
+                        writeln!(out, "watching [{}]", address)?;
+                    }
+                    None => writeln!(out, "invalid address")?,
+                },
+                ("u", 1) | ("unwatch", 1) => match address(0) {
+                    Some(address) if self.remove_watchpoint(address) => writeln!(out, "unwatched [{}]", address)?,
+                    _ => writeln!(out, "no watchpoint there")?,
+                },
+                ("r", 0) | ("regs", 0) => {
+                    writeln!(out, "idx={} rb={} halted={} input={:?}",
+                             self.comp.idx, self.comp.relative_base, self.comp.halted, self.comp.input)?;
+                }
+                ("x", 1) | ("x", 2) => match address(0) {
+                    Some(start) => {
+                        let words: Vec<String> = (start..start + arg(1).unwrap_or(1).max(1) as usize)
+                            .map(|a| self.peek(a).to_string())
+                            .collect();
+                        writeln!(out, "[{}] {}", start, words.join(" "))?;
+                    }
+                    None => writeln!(out, "invalid address")?,
+                },
+                ("set", 2) => match address(0) {
+                    Some(address) => self.poke(address, args[1]),
+                    None => writeln!(out, "invalid address")?,
+                },
+                ("i", _) | ("input", _) => args.iter().for_each(|&v| self.push_input(v)),
+                ("l", _) | ("list", _) => {
+                    let mut idx = address(0).unwrap_or(self.comp.idx);
+                    for _ in 0..arg(1).unwrap_or(5).max(1) {
+                        let (text, len) = self.comp.disassemble_at(idx);
+                        writeln!(out, "{} {:>5}  {}", self.marker(idx), idx, text)?;
+                        idx += len;
+                    }
+                }
+                ("o", 0) | ("outputs", 0) => writeln!(out, "{:?}", self.outputs)?,
+                ("q", 0) | ("quit", 0) => return Ok(()),
+                ("h", 0) | ("help", 0) => out.write_all(HELP.as_bytes())?,
+                _ => writeln!(out, "unknown command {:?}, try help", line.trim())?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prints the outcome of a step or continue, returning whether execution can proceed.
+    fn report<W: Write>(&self, out: &mut W, stop: Result<Stop, IntCodeError>) -> io::Result<bool> {
+        match stop {
+            Ok(Stop::Step) => {}
+            Ok(Stop::Breakpoint(idx)) => writeln!(out, "breakpoint at {}", idx)?,
+            Ok(Stop::Watchpoint { idx, address, old, new }) =>
+                writeln!(out, "watchpoint [{}] written by {}: {} -> {}", address, idx, old, new)?,
+            Ok(Stop::NeedsInput) => {
+                writeln!(out, "waiting for input")?;
+                return Ok(false);
+            }
+            Ok(Stop::Halted) => {
+                writeln!(out, "halted")?;
+                return Ok(false);
+            }
+            Err(err) => {
+                writeln!(out, "error: {}", err)?;
+                return Ok(false);
+            }
+        }
+
+        self.print_current(out)?;
+        Ok(true)
+    }
+
+    fn print_current<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let (text, _) = self.comp.disassemble_at(self.comp.idx);
+        writeln!(out, "{} {:>5}  {}", self.marker(self.comp.idx), self.comp.idx, text)
+    }
+
+    fn marker(&self, idx: usize) -> char {
+        match (idx == self.comp.idx, self.breakpoints.contains(&idx)) {
+            (true, _) => '>',
+            (false, true) => '*',
+            (false, false) => ' ',
+        }
+    }
+}
+
+const HELP: &str = "\
+s, step [n]          execute n instructions (default 1)
+c, continue          run until a breakpoint, watchpoint, input wait or halt
+rs, rstep [n]        undo n instructions (default 1)
+rc, rcontinue <addr> run backward to the last instruction that wrote an address
+b, break <idx>       set a breakpoint on an instruction address
+d, delete <idx>      remove a breakpoint
+w, watch <addr>      stop when an address is written
+u, unwatch <addr>    remove a watchpoint
+r, regs              show the instruction pointer, relative base and input queue
+x <addr> [n]         examine n words of memory
+set <addr> <value>   write a word of memory
+i, input <v>...      queue input values
+l, list [idx] [n]    disassemble n instructions (default from idx)
+o, outputs           show outputs produced so far
+q, quit              leave the debugger
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::countdown;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_step_and_watch() {
+        let mut dbg = Debugger::new(countdown());
+        dbg.add_watchpoint(12);
+
+        assert_eq!(dbg.step(), Ok(Stop::Step));
+        assert_eq!(dbg.outputs(), &[3]);
+        assert_eq!(dbg.idx(), 2);
+        assert_eq!(dbg.step(), Ok(Stop::Watchpoint { idx: 2, address: 12, old: 3, new: 2 }));
+        assert_eq!(dbg.cont(), Ok(Stop::Watchpoint { idx: 2, address: 12, old: 2, new: 1 }));
+
+        dbg.remove_watchpoint(12);
+        assert_eq!(dbg.cont(), Ok(Stop::Halted));
+        assert_eq!(dbg.outputs(), &[3, 2, 1]);
+        assert_eq!(dbg.relative_base(), 0);
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let mut dbg = Debugger::new(countdown());
+        dbg.add_breakpoint(0);
+
+        assert_eq!(dbg.cont(), Ok(Stop::Breakpoint(0)));
+        assert_eq!(dbg.peek(12), 2);
+        dbg.poke(12, 5);
+        assert_eq!(dbg.cont(), Ok(Stop::Breakpoint(0)));
+        assert!(dbg.remove_breakpoint(0));
+        assert_eq!(dbg.cont(), Ok(Stop::Halted));
+        assert_eq!(dbg.outputs(), &[3, 5, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn test_needs_input() {
+        let mut dbg = Debugger::new(IntCodeComputer::new(vec![3, 5, 4, 5, 99, 0], None));
+        assert_eq!(dbg.cont(), Ok(Stop::NeedsInput));
+        assert_eq!(dbg.idx(), 0);
+        dbg.push_input(7);
+        assert_eq!(dbg.cont(), Ok(Stop::Halted));
+        assert_eq!(dbg.outputs(), &[7]);
+    }
+
+    #[test]
+    fn test_repl() {
+        let mut dbg = Debugger::new(countdown());
+        let commands = "b 6\nc\nr\nx 12 2\nw 12\nstep 2\nlist 0 2\nbogus\nd 6\nc\no\nq\ns\n";
+        let mut out = Vec::new();
+        dbg.repl(Cursor::new(commands), &mut out).unwrap();
+
+        let expected = "\
+>     0  OUT [12]
+added breakpoint at 6
+breakpoint at 6
+>     6  JT [12] #0
+idx=6 rb=0 halted=false input=[]
+[12] 2 0
+watching [12]
+>     0  OUT [12]
+>     2  ADD [12] #-1 -> [12]
+      0  OUT [12]
+>     2  ADD [12] #-1 -> [12]
+unknown command \"bogus\", try help
+deleted breakpoint at 6
+watchpoint [12] written by 2: 2 -> 1
+>     6  JT [12] #0
+[3, 2]
+";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+        assert_eq!(dbg.idx(), 6);
+    }
+
+    #[test]
+    fn test_reverse() {
+        let mut dbg = Debugger::with_history(countdown(), 100);
+        assert_eq!(dbg.cont(), Ok(Stop::Halted));
+        assert_eq!(dbg.step_back(), Some(9));
+        assert_eq!(dbg.step_back(), Some(6));
+
+        let change = dbg.reverse_to_write(12).unwrap();
+        assert_eq!((change.idx, change.write), (2, Some((12, 1))));
+        assert_eq!(dbg.outputs(), &[3, 2, 1]);
+        assert_eq!(dbg.reverse_to_write(12).map(|c| c.write), Some(Some((12, 2))));
+        assert_eq!(dbg.outputs(), &[3, 2]);
+
+        let mut out = Vec::new();
+        dbg.repl(Cursor::new("rc 12
+rc 12
+rs
+c
+"), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\
+>     2  ADD [12] #-1 -> [12]
+[12] last written here, was 3
+>     2  ADD [12] #-1 -> [12]
+start of history, [12] not written
+>     0  OUT [12]
+start of history
+>     0  OUT [12]
+halted
+");
+        assert_eq!(dbg.outputs(), &[3, 2, 1]);
+        assert_eq!(dbg.into_computer().history_len(), 0);
+    }
+
+    #[test]
+    fn test_history_is_opt_in() {
+        let mut dbg = Debugger::new(countdown());
+        assert_eq!(dbg.cont(), Ok(Stop::Halted));
+        assert_eq!(dbg.step_back(), None);
+
+        let mut out = Vec::new();
+        dbg.repl(Cursor::new("rs\n"), &mut out).unwrap();
+        assert!(String::from_utf8(out).unwrap().ends_with("history is off, start the debugger with history to step backward\n"));
+
+        let mut comp = countdown();
+        comp.enable_history(10);
+        let mut dbg = Debugger::with_history(comp, 100);
+        assert_eq!(dbg.cont(), Ok(Stop::Halted));
+        assert_eq!(dbg.into_computer().history_len(), 10);
+    }
+}
@@ -0,0 +1,80 @@
+use super::{IntCodeComputer, Mode, Op};
+
+impl IntCodeComputer {
+    /// Renders memory as an assembly listing, one instruction per line. Words that do not
+    /// decode into a complete instruction are listed individually as `DATA`. Sparse memory
+    /// only lists the words that have been set.
+    pub fn disassemble(&self) -> String {
+        let mut out = String::new();
+        let mut next = 0;
+
+        for idx in self.data.addresses() {
+            if idx < next {
+                continue;
+            }
+            let (text, len) = self.disassemble_at(idx);
+            out.push_str(&format!("{:>5}  {}\n", idx, text));
+            next = idx + len;
+        }
+
+        out
+    }
+
+    /// Decodes the instruction at `idx`, returning its text and length in words.
+    pub fn disassemble_at(&self, idx: usize) -> (String, usize) {
+        let code = self.read(idx);
+        let op = match Op::decode(code, idx) {
+            Ok(op) if idx + op.len() <= self.len() => op,
+            _ => return (format!("DATA {}", code), 1),
+        };
+
+        let modes = op.modes();
+        let mut text = op.mnemonic().to_string();
+
+        for (i, &mode) in modes.iter().enumerate() {
+            if op.writes() && i == modes.len() - 1 {
+                text.push_str(" ->");
+            }
+            text.push(' ');
+            text.push_str(&operand(mode, self.read(idx + i + 1)));
+        }
+
+        (text, op.len())
+    }
+}
+
+fn operand(mode: Mode, value: i64) -> String {
+    match mode {
+        Mode::Position => format!("[{}]", value),
+        Mode::Immediate => format!("#{}", value),
+        Mode::Relative if value < 0 => format!("[rb{}]", value),
+        Mode::Relative => format!("[rb+{}]", value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disassemble() {
+        let comp = IntCodeComputer::new(vec![1002, 12, 3, 7, 109, -4, 21107, 1, 2, -1, 99, 42, 1105], None);
+        let expected = "    0  MUL [12] #3 -> [7]
+    4  ARB #-4
+    6  LT #1 #2 -> [rb-1]
+   10  HLT
+   11  DATA 42
+   12  DATA 1105
+";
+        assert_eq!(comp.disassemble(), expected);
+    }
+
+    #[test]
+    fn test_disassemble_at() {
+        let comp = IntCodeComputer::new(vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8], None);
+        assert_eq!(comp.disassemble_at(0), ("IN -> [9]".to_string(), 2));
+        assert_eq!(comp.disassemble_at(2), ("EQ [9] [10] -> [9]".to_string(), 4));
+        assert_eq!(comp.disassemble_at(6), ("OUT [9]".to_string(), 2));
+        assert_eq!(comp.disassemble_at(9), ("DATA -1".to_string(), 1));
+    }
+}
@@ -0,0 +1,56 @@
+use std::error::Error;
+use std::fmt;
+
+/// A fault raised while executing an Intcode program. Each variant carries the
+/// address of the instruction that caused it.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum IntCodeError {
+    UnknownOpcode { idx: usize, opcode: i64 },
+    InvalidMode { idx: usize, opcode: i64 },
+    ImmediateWrite { idx: usize },
+    NegativeAddress { idx: usize, address: i64 },
+    OutOfBounds { idx: usize },
+    InputExhausted { idx: usize },
+    Overflow { idx: usize },
+    StepLimit { idx: usize },
+    Timeout { idx: usize },
+    NoProgress { idx: usize },
+}
+
+impl IntCodeError {
+    pub fn idx(self) -> usize {
+        use IntCodeError::*;
+        match self {
+            UnknownOpcode { idx, .. } => idx,
+            InvalidMode { idx, .. } => idx,
+            ImmediateWrite { idx } => idx,
+            NegativeAddress { idx, .. } => idx,
+            OutOfBounds { idx } => idx,
+            InputExhausted { idx } => idx,
+            Overflow { idx } => idx,
+            StepLimit { idx } => idx,
+            Timeout { idx } => idx,
+            NoProgress { idx } => idx,
+        }
+    }
+}
+
+impl fmt::Display for IntCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use IntCodeError::*;
+        match *self {
+            UnknownOpcode { idx, opcode } => write!(f, "unknown opcode {} at idx {}", opcode, idx),
+            InvalidMode { idx, opcode } => write!(f, "invalid parameter mode in opcode {} at idx {}", opcode, idx),
+            ImmediateWrite { idx } => write!(f, "write target in immediate mode at idx {}", idx),
+            NegativeAddress { idx, address } => write!(f, "negative address {} at idx {}", address, idx),
+            OutOfBounds { idx } => write!(f, "instruction pointer out of bounds at idx {}", idx),
+            InputExhausted { idx } => write!(f, "input exhausted at idx {}", idx),
+            Overflow { idx } => write!(f, "arithmetic overflow at idx {}", idx),
+            StepLimit { idx } => write!(f, "step limit reached at idx {}", idx),
+            Timeout { idx } => write!(f, "deadline passed at idx {}", idx),
+            NoProgress { idx } => write!(f, "infinite loop without progress at idx {}", idx),
+        }
+    }
+}
+
+impl Error for IntCodeError {}
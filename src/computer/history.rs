@@ -0,0 +1,211 @@
+use super::{IntCodeComputer, Op};
+use std::collections::VecDeque;
+
+/// One executed instruction and everything needed to undo it: where it ran, the relative base
+/// before it ran, the word it overwrote, and the input it consumed.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Change {
+    pub idx: usize,
+    pub relative_base: i64,
+    /// The address written and the value it held before.
+    pub write: Option<(usize, i64)>,
+    pub input: Option<i64>,
+    pub output: Option<i64>,
+}
+
+/// An undo log of the most recent `limit` instructions.
+#[derive(Clone, Debug)]
+pub(super) struct History {
+    changes: VecDeque<Change>,
+    limit: usize,
+    outputs: usize,
+    write: Option<(usize, i64)>,
+}
+
+impl History {
+    pub(super) fn record_write(&mut self, address: usize, old: i64) { self.write = Some((address, old)); }
+}
+
+impl IntCodeComputer {
+    /// Starts keeping an undo log of the last `limit` instructions so execution can be stepped
+    /// backward. Writes made through indexing, rather than by the program, are not logged.
+    pub fn enable_history(&mut self, limit: usize) {
+        match self.history.as_mut() {
+            Some(history) => history.limit = limit,
+            None => self.history = Some(History { changes: VecDeque::new(), limit, outputs: 0, write: None }),
+        }
+        self.trim_history();
+    }
+
+    pub fn disable_history(&mut self) { self.history = None; }
+
+    /// The number of instructions that can currently be undone.
+    pub fn history_len(&self) -> usize { self.history.as_ref().map_or(0, |h| h.changes.len()) }
+
+    /// The number of logged instructions that produced an output.
+    pub fn history_outputs(&self) -> usize { self.history.as_ref().map_or(0, |h| h.outputs) }
+
+    pub fn last_change(&self) -> Option<&Change> { self.history.as_ref()?.changes.back() }
+
+    /// Undoes the most recent instruction, restoring memory, the instruction pointer, the
+    /// relative base and any consumed input. Outputs already handed out cannot be taken back,
+    /// but the returned change says which one the instruction produced. Jumps seen by loop
+    /// detection are forgotten, since they may lie in the undone future.
+    pub fn step_back(&mut self) -> Option<Change> {
+        let history = self.history.as_mut()?;
+        let change = history.changes.pop_back()?;
+        if change.output.is_some() {
+            history.outputs -= 1;
+        }
+
+        if let Some((address, old)) = change.write {
+            self[address] = old;
+        }
+        if let Some(input) = change.input {
+            self.input.push_front(input);
+        }
+        self.idx = change.idx;
+        self.relative_base = change.relative_base;
+        self.halted = false;
+        if let Some(budget) = self.budget.as_mut() {
+            budget.progressed();
+        }
+        Some(change)
+    }
+
+    /// Steps backward until the most recent instruction that wrote `address` has been undone,
+    /// leaving the computer about to execute it again. Rewinds to the oldest logged instruction
+    /// and returns `None` if no logged instruction wrote there.
+    pub fn reverse_to_write(&mut self, address: usize) -> Option<Change> {
+        while let Some(change) = self.step_back() {
+            if matches!(change.write, Some((a, _)) if a == address) {
+                return Some(change);
+            }
+        }
+        None
+    }
+
+    pub(super) fn begin_change(&mut self) -> Change {
+        if let Some(history) = self.history.as_mut() {
+            history.write = None;
+        }
+        let input = match Op::decode(self.read(self.idx), self.idx) {
+            Ok(Op::Input(_)) => self.input.front().copied(),
+            _ => None,
+        };
+        Change { idx: self.idx, relative_base: self.relative_base, write: None, input, output: None }
+    }
+
+    pub(super) fn end_change(&mut self, mut change: Change, output: Option<i64>) {
+        if let Some(history) = self.history.as_mut() {
+            change.write = history.write.take();
+            change.output = output;
+            history.outputs += output.is_some() as usize;
+            history.changes.push_back(change);
+        }
+        self.trim_history();
+    }
+
+    fn trim_history(&mut self) {
+        if let Some(history) = self.history.as_mut() {
+            while history.changes.len() > history.limit {
+                if let Some(Change { output: Some(_), .. }) = history.changes.pop_front() {
+                    history.outputs -= 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::limits::Limits;
+    use crate::computer::{countdown, State};
+
+    #[test]
+    fn test_step_back() {
+        let mut comp = countdown();
+        comp.enable_history(100);
+        assert_eq!(comp.try_run(), Ok(vec![3, 2, 1]));
+        assert_eq!(comp.history_len(), 10);
+        assert_eq!(comp.history_outputs(), 3);
+        assert_eq!(comp[12], 0);
+
+        assert_eq!(comp.step_back().map(|c| c.idx), Some(9));
+        assert!(!comp.is_halted());
+        assert_eq!(comp.step_back().map(|c| c.idx), Some(6));
+        assert_eq!(comp.step_back(), Some(Change {
+            idx: 2,
+            relative_base: 0,
+            write: Some((12, 1)),
+            input: None,
+            output: None,
+        }));
+        assert_eq!(comp[12], 1);
+        assert_eq!(comp.idx, 2);
+        assert_eq!(comp.try_run(), Ok(vec![]));
+        assert_eq!(comp[12], 0);
+    }
+
+    #[test]
+    fn test_reverse_to_write() {
+        let mut comp = countdown();
+        comp.enable_history(100);
+        comp.run();
+
+        let change = comp.reverse_to_write(12).expect("[12] was written");
+        assert_eq!((change.idx, change.write), (2, Some((12, 1))));
+        assert_eq!(comp.reverse_to_write(12).map(|c| c.write), Some(Some((12, 2))));
+        assert_eq!(comp.reverse_to_write(12).map(|c| c.write), Some(Some((12, 3))));
+        assert_eq!(comp.reverse_to_write(12), None);
+        assert_eq!(comp.history_len(), 0);
+        assert_eq!(comp.idx, 0);
+        assert_eq!(comp.collect::<Vec<_>>(), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn test_undo_input_and_self_modification() {
+        // Reads an opcode into [2], then runs it: 104 outputs 7, 99 halts.
+        let mut comp = IntCodeComputer::new(vec![3, 2, 0, 7, 99], vec![104]);
+        comp.enable_history(100);
+        assert_eq!(comp.resume(), Ok(State::Output(7)));
+        assert_eq!(comp.last_change().and_then(|c| c.output), Some(7));
+
+        let change = comp.reverse_to_write(2).unwrap();
+        assert_eq!(change.input, Some(104));
+        assert_eq!(comp[2], 0);
+        assert_eq!(comp.input, vec![104]);
+
+        comp.input[0] = 99;
+        assert_eq!(comp.resume(), Ok(State::Halted));
+        assert_eq!(comp.idx, 2);
+    }
+
+    #[test]
+    fn test_history_limit() {
+        let mut comp = countdown();
+        comp.enable_history(2);
+        comp.run();
+        assert_eq!(comp.history_len(), 2);
+        assert_eq!(comp.reverse_to_write(12), None);
+        assert_eq!(comp.idx, 6);
+
+        comp.disable_history();
+        comp.run();
+        assert_eq!(comp.step_back(), None);
+    }
+
+    #[test]
+    fn test_step_back_with_loop_detection() {
+        let mut comp = IntCodeComputer::new(vec![1105, 1, 3, 99], None);
+        comp.enable_history(10);
+        comp.set_limits(Limits { detect_loops: true, ..Limits::default() });
+
+        assert_eq!(comp.try_step(), Ok(None));
+        assert!(comp.step_back().is_some());
+        assert_eq!(comp.try_step(), Ok(None));
+        assert_eq!(comp.try_step(), Ok(None));
+        assert!(comp.is_halted());
+    }
+}
@@ -0,0 +1,270 @@
+use super::{IntCodeComputer, IntCodeError, State};
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{Receiver, Sender, SyncSender};
+use std::sync::{Mutex, PoisonError};
+
+/// Supplies values to a computer's `Input` instructions. Returning `None` means no value is
+/// available, which pauses the computer until more input arrives.
+pub trait InputSource {
+    fn next_input(&mut self) -> Option<i64>;
+}
+
+/// Receives the values produced by a computer's `Output` instructions.
+pub trait OutputSink {
+    fn send_output(&mut self, value: i64);
+}
+
+/// A source or sink attached to a computer. The mutex is never locked, since the computer has
+/// exclusive access; it only keeps the computer `Sync` when the source or sink is not. Clones
+/// start detached, because a source or sink cannot be shared.
+pub(super) struct Port<T: ?Sized>(Option<Mutex<Box<T>>>);
+
+impl<T: ?Sized> Port<T> {
+    pub(super) fn is_attached(&self) -> bool { self.0.is_some() }
+
+    fn get_mut(&mut self) -> Option<&mut T> {
+        self.0.as_mut().map(|m| &mut **m.get_mut().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn take(&mut self) -> Option<Box<T>> {
+        self.0.take().map(|m| m.into_inner().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+impl<T: ?Sized> Default for Port<T> {
+    fn default() -> Self { Port(None) }
+}
+
+impl<T: ?Sized> Clone for Port<T> {
+    fn clone(&self) -> Self { Port(None) }
+}
+
+impl IntCodeComputer {
+    /// Attaches a source that `Input` instructions read from whenever the internal queue is
+    /// empty. The computer pauses for input only once the source returns `None`.
+    pub fn attach_input<I: InputSource + Send + 'static>(&mut self, source: I) {
+        self.source = Port(Some(Mutex::new(Box::new(source))));
+    }
+
+    pub fn detach_input(&mut self) -> Option<Box<dyn InputSource + Send>> { self.source.take() }
+
+    /// Attaches a sink that receives every output as it is produced. While a sink is attached,
+    /// `resume` and iteration no longer stop to hand outputs back.
+    pub fn attach_output<O: OutputSink + Send + 'static>(&mut self, sink: O) {
+        self.sink = Port(Some(Mutex::new(Box::new(sink))));
+    }
+
+    pub fn detach_output(&mut self) -> Option<Box<dyn OutputSink + Send>> { self.sink.take() }
+
+    pub(super) fn pull_input(&mut self) {
+        if let Some(value) = self.source.get_mut().and_then(|source| source.next_input()) {
+            self.input.push_back(value);
+        }
+    }
+
+    pub(super) fn push_output(&mut self, value: i64) {
+        if let Some(sink) = self.sink.get_mut() {
+            sink.send_output(value);
+        }
+    }
+
+    /// Runs until the program halts or `input` runs dry, pulling inputs from `input` once the
+    /// internal queue is empty and pushing every output into `output`. Unlike an attached
+    /// source or sink, `input` and `output` may borrow from the caller.
+    pub fn run_io<I: InputSource, O: OutputSink>(&mut self, mut input: I, mut output: O) -> Result<State, IntCodeError> {
+        loop {
+            match self.resume()? {
+                State::Output(value) => output.send_output(value),
+                State::NeedsInput => match input.next_input() {
+                    Some(value) => self.push_input(value),
+                    None => return Ok(State::NeedsInput),
+                },
+                State::Halted => return Ok(State::Halted),
+            }
+        }
+    }
+}
+
+/// Adapts any iterator of values into an input source.
+pub struct IterInput<I>(pub I);
+
+impl<I: Iterator<Item=i64>> InputSource for IterInput<I> {
+    fn next_input(&mut self) -> Option<i64> { self.0.next() }
+}
+
+/// Adapts a closure into an input source.
+pub struct FnInput<F>(pub F);
+
+impl<F: FnMut() -> Option<i64>> InputSource for FnInput<F> {
+    fn next_input(&mut self) -> Option<i64> { (self.0)() }
+}
+
+/// Adapts a closure into an output sink.
+pub struct FnOutput<F>(pub F);
+
+impl<F: FnMut(i64)> OutputSink for FnOutput<F> {
+    fn send_output(&mut self, value: i64) { (self.0)(value) }
+}
+
+/// Reads one integer per line from stdin, stopping at end of input or an unparseable line.
+pub struct StdinInput;
+
+impl InputSource for StdinInput {
+    fn next_input(&mut self) -> Option<i64> {
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line).ok()?;
+        line.trim().parse().ok()
+    }
+}
+
+/// Writes one integer per line to stdout.
+pub struct StdoutOutput;
+
+impl OutputSink for StdoutOutput {
+    fn send_output(&mut self, value: i64) {
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        writeln!(out, "{}", value).and_then(|_| out.flush()).expect("failed to write to stdout");
+    }
+}
+
+impl InputSource for VecDeque<i64> {
+    fn next_input(&mut self) -> Option<i64> { self.pop_front() }
+}
+
+/// Blocks until a value arrives, returning `None` once every sender has hung up.
+impl InputSource for Receiver<i64> {
+    fn next_input(&mut self) -> Option<i64> { self.recv().ok() }
+}
+
+impl<T: InputSource + ?Sized> InputSource for &mut T {
+    fn next_input(&mut self) -> Option<i64> { (**self).next_input() }
+}
+
+impl<T: InputSource + ?Sized> InputSource for Box<T> {
+    fn next_input(&mut self) -> Option<i64> { (**self).next_input() }
+}
+
+impl OutputSink for Vec<i64> {
+    fn send_output(&mut self, value: i64) { self.push(value) }
+}
+
+impl OutputSink for VecDeque<i64> {
+    fn send_output(&mut self, value: i64) { self.push_back(value) }
+}
+
+/// Values sent after the receiver hangs up are dropped.
+impl OutputSink for Sender<i64> {
+    fn send_output(&mut self, value: i64) { self.send(value).ok(); }
+}
+
+/// Values sent after the receiver hangs up are dropped.
+impl OutputSink for SyncSender<i64> {
+    fn send_output(&mut self, value: i64) { self.send(value).ok(); }
+}
+
+impl<T: OutputSink + ?Sized> OutputSink for &mut T {
+    fn send_output(&mut self, value: i64) { (**self).send_output(value) }
+}
+
+impl<T: OutputSink + ?Sized> OutputSink for Box<T> {
+    fn send_output(&mut self, value: i64) { (**self).send_output(value) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::assembler::assemble;
+    use std::sync::mpsc::channel;
+
+    // Outputs double each input until a zero is read.
+    fn doubler() -> IntCodeComputer {
+        let program = assemble("
+            loop:   IN -> [x]
+                    JF [x] #done
+                    MUL [x] #2 -> [x]
+                    OUT [x]
+                    JT #1 #loop
+            done:   HLT
+            x:      .data 0
+        ").unwrap();
+        IntCodeComputer::new(program, None)
+    }
+
+    #[test]
+    fn test_iter_and_vec() {
+        let mut out = Vec::new();
+        let state = doubler().run_io(IterInput(vec![1, 2, 3].into_iter()), &mut out);
+        assert_eq!(state, Ok(State::NeedsInput));
+        assert_eq!(out, vec![2, 4, 6]);
+
+        let mut out = VecDeque::new();
+        let state = doubler().run_io(IterInput(vec![1, 2, 0].into_iter()), &mut out);
+        assert_eq!(state, Ok(State::Halted));
+        assert_eq!(out, vec![2, 4]);
+    }
+
+    #[test]
+    fn test_deque_and_closures() {
+        let mut input: VecDeque<i64> = vec![5, 6].into_iter().collect();
+        let mut seen = Vec::new();
+        let mut comp = doubler();
+
+        assert_eq!(comp.run_io(&mut input, FnOutput(|v| seen.push(v))), Ok(State::NeedsInput));
+        assert!(input.is_empty());
+
+        let mut calls = 0;
+        let input = FnInput(|| {
+            calls += 1;
+            Some(3 - calls)
+        });
+        assert_eq!(comp.run_io(input, FnOutput(|v| seen.push(v))), Ok(State::Halted));
+        assert_eq!(seen, vec![10, 12, 4, 2]);
+    }
+
+    #[test]
+    fn test_channels() {
+        let (in_tx, in_rx) = channel();
+        let (out_tx, out_rx) = channel();
+
+        for value in &[7, 8, 0] {
+            in_tx.send(*value).unwrap();
+        }
+        drop(in_tx);
+
+        assert_eq!(doubler().run_io(in_rx, out_tx), Ok(State::Halted));
+        assert_eq!(out_rx.iter().collect::<Vec<_>>(), vec![14, 16]);
+    }
+
+    #[test]
+    fn test_boxed() {
+        let mut out = Vec::new();
+        let mut input: Box<dyn InputSource> = Box::new(IterInput(vec![4, 0].into_iter()));
+        let mut output: Box<dyn OutputSink + '_> = Box::new(&mut out);
+        assert_eq!(doubler().run_io(&mut input, &mut output), Ok(State::Halted));
+        drop(output);
+        assert_eq!(out, vec![8]);
+    }
+
+    #[test]
+    fn test_attached() {
+        let (out_tx, out_rx) = channel();
+        let mut comp = doubler();
+        comp.attach_input(IterInput(vec![1, 2].into_iter()));
+        comp.attach_output(out_tx);
+
+        assert_eq!(comp.resume(), Ok(State::NeedsInput));
+        assert_eq!(out_rx.try_iter().collect::<Vec<_>>(), vec![2, 4]);
+
+        let mut clone = comp.clone();
+        assert_eq!(clone.resume_with(5), Ok(State::Output(10)));
+        assert_eq!(comp.resume_with(6), Ok(State::NeedsInput));
+        assert_eq!(out_rx.try_iter().collect::<Vec<_>>(), vec![12]);
+
+        assert!(comp.detach_input().is_some());
+        assert!(comp.detach_output().is_some());
+        assert_eq!(comp.resume_with(7), Ok(State::Output(14)));
+        assert_eq!(comp.resume_with(0), Ok(State::Halted));
+    }
+}
@@ -0,0 +1,158 @@
+use super::{IntCodeComputer, IntCodeError};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// How many steps pass between checks of the wall clock.
+const CLOCK_INTERVAL: u64 = 1 << 10;
+
+/// Bounds on how long a computer may run before it stops with an error.
+#[derive(PartialEq, Copy, Clone, Debug, Default)]
+pub struct Limits {
+    /// Stop with `IntCodeError::StepLimit` once this many instructions have executed.
+    pub max_steps: Option<u64>,
+    /// Stop with `IntCodeError::Timeout` once this much time has passed. The clock is only
+    /// read once every 1024 steps, so a deadline can be overshot slightly.
+    pub timeout: Option<Duration>,
+    /// Stop with `IntCodeError::NoProgress` when a jump returns to a target it has already
+    /// reached with the same relative base, and no memory write, input or output has happened
+    /// in between. The machine is then in exactly the same state, so it can never halt.
+    pub detect_loops: bool,
+}
+
+#[derive(Clone)]
+pub(super) struct Budget {
+    limits: Limits,
+    steps: u64,
+    deadline: Option<Instant>,
+    visited: HashSet<(usize, i64)>,
+}
+
+impl Budget {
+    fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            steps: 0,
+            deadline: limits.timeout.map(|timeout| Instant::now() + timeout),
+            visited: HashSet::new(),
+        }
+    }
+
+    pub(super) fn check(&self, idx: usize) -> Result<(), IntCodeError> {
+        if self.limits.max_steps.is_some_and(|max| self.steps >= max) {
+            return Err(IntCodeError::StepLimit { idx });
+        }
+
+        if let Some(deadline) = self.deadline {
+            if self.steps % CLOCK_INTERVAL == 0 && Instant::now() >= deadline {
+                return Err(IntCodeError::Timeout { idx });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn record(&mut self, produced_output: bool) {
+        self.steps += 1;
+        if produced_output {
+            self.progressed();
+        }
+    }
+
+    pub(super) fn progressed(&mut self) {
+        if !self.visited.is_empty() {
+            self.visited.clear();
+        }
+    }
+
+    pub(super) fn jumped(&mut self, idx: usize, target: usize, relative_base: i64) -> Result<(), IntCodeError> {
+        if self.limits.detect_loops && !self.visited.insert((target, relative_base)) {
+            return Err(IntCodeError::NoProgress { idx });
+        }
+        Ok(())
+    }
+}
+
+impl IntCodeComputer {
+    /// Applies `limits` from this point on, restarting the step count and the clock.
+    pub fn set_limits(&mut self, limits: Limits) { self.budget = Some(Budget::new(limits)); }
+
+    pub fn clear_limits(&mut self) { self.budget = None; }
+
+    /// The number of instructions executed since limits were last set.
+    pub fn steps(&self) -> Option<u64> { self.budget.as_ref().map(|b| b.steps) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::assembler::assemble;
+
+    fn limited(program: Vec<i64>, limits: Limits) -> IntCodeComputer {
+        let mut comp = IntCodeComputer::new(program, None);
+        comp.set_limits(limits);
+        comp
+    }
+
+    #[test]
+    fn test_step_limit() {
+        let limits = Limits { max_steps: Some(3), ..Limits::default() };
+
+        let mut comp = limited(vec![1105, 1, 0], limits);
+        assert_eq!(comp.try_run(), Err(IntCodeError::StepLimit { idx: 0 }));
+        assert_eq!(comp.steps(), Some(3));
+
+        let mut comp = limited(vec![104, 1, 104, 2, 99], limits);
+        assert_eq!(comp.try_run(), Ok(vec![1, 2]));
+
+        let mut comp = limited(vec![3, 0, 99], limits);
+        assert_eq!(comp.try_run(), Err(IntCodeError::InputExhausted { idx: 0 }));
+        assert_eq!(comp.steps(), Some(0));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let limits = Limits { timeout: Some(Duration::from_millis(0)), ..Limits::default() };
+        let mut comp = limited(vec![1105, 1, 0], limits);
+        assert_eq!(comp.try_run(), Err(IntCodeError::Timeout { idx: 0 }));
+    }
+
+    #[test]
+    fn test_detect_loops() {
+        let limits = Limits { detect_loops: true, ..Limits::default() };
+
+        let mut comp = limited(vec![1105, 1, 0], limits);
+        assert_eq!(comp.try_run(), Err(IntCodeError::NoProgress { idx: 0 }));
+        assert_eq!(comp.steps(), Some(1));
+
+        // Bounces between two jumps without touching memory.
+        let program = assemble("
+            a:  JT #1 #b
+            b:  JF #0 #a
+        ").unwrap();
+        let mut comp = limited(program, limits);
+        assert_eq!(comp.try_run(), Err(IntCodeError::NoProgress { idx: 0 }));
+
+        // A countdown loop writes on every iteration, so it is never flagged.
+        let program = assemble("
+            top:    ADD [n] #-1 -> [n]
+                    JT [n] #top
+                    HLT
+            n:      .data 1000
+        ").unwrap();
+        let mut comp = limited(program, limits);
+        assert_eq!(comp.try_run(), Ok(vec![]));
+
+        // An output loop makes progress even though memory never changes.
+        let mut comp = limited(vec![104, 7, 1105, 1, 0], Limits { max_steps: Some(10), ..limits });
+        assert_eq!(comp.try_run(), Err(IntCodeError::StepLimit { idx: 0 }));
+    }
+
+    #[test]
+    fn test_clear_limits() {
+        let mut comp = limited(vec![1105, 1, 0], Limits { max_steps: Some(0), ..Limits::default() });
+        assert_eq!(comp.try_step(), Err(IntCodeError::StepLimit { idx: 0 }));
+        comp.clear_limits();
+        assert_eq!(comp.try_step(), Ok(None));
+        assert_eq!(comp.steps(), None);
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+/// How far past the end of dense memory a write may land before the storage switches to the
+/// sparse backend instead of zero-filling the gap.
+const SPARSE_GAP: usize = 1 << 20;
+
+/// Word-addressed program memory. Unset addresses read as zero, and writing to any address
+/// makes it part of memory.
+pub trait Memory {
+    /// One past the highest address that has been set.
+    fn len(&self) -> usize;
+
+    fn get(&self, address: usize) -> &i64;
+
+    fn get_mut(&mut self, address: usize) -> &mut i64;
+}
+
+impl Memory for Vec<i64> {
+    fn len(&self) -> usize { Vec::len(self) }
+
+    fn get(&self, address: usize) -> &i64 { self.as_slice().get(address).unwrap_or(&0) }
+
+    fn get_mut(&mut self, address: usize) -> &mut i64 {
+        if address >= Vec::len(self) {
+            self.resize(address + 1, 0);
+        }
+        &mut self[address]
+    }
+}
+
+/// Stores only the addresses that have been written, for programs that touch a few words far
+/// apart.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct SparseMemory {
+    cells: HashMap<usize, i64>,
+    len: usize,
+}
+
+impl SparseMemory {
+    /// Creates empty memory that reports `len` words until something is set beyond it.
+    pub fn with_len(len: usize) -> Self { Self { cells: HashMap::new(), len } }
+
+    /// The set addresses and their values, in address order.
+    pub fn cells(&self) -> Vec<(usize, i64)> {
+        let mut cells: Vec<(usize, i64)> = self.cells.iter().map(|(&a, &v)| (a, v)).collect();
+        cells.sort_unstable();
+        cells
+    }
+}
+
+impl From<Vec<i64>> for SparseMemory {
+    fn from(data: Vec<i64>) -> Self {
+        let len = data.len();
+        let cells = data.into_iter().enumerate().filter(|&(_, v)| v != 0).collect();
+        Self { cells, len }
+    }
+}
+
+impl Memory for SparseMemory {
+    fn len(&self) -> usize { self.len }
+
+    fn get(&self, address: usize) -> &i64 { self.cells.get(&address).unwrap_or(&0) }
+
+    fn get_mut(&mut self, address: usize) -> &mut i64 {
+        self.len = self.len.max(address + 1);
+        self.cells.entry(address).or_insert(0)
+    }
+}
+
+/// The backend chosen for a computer. Dense storage moves to the sparse backend on its own
+/// when a write lands far beyond its end.
+#[derive(PartialEq, Clone, Debug)]
+pub enum Storage {
+    Dense(Vec<i64>),
+    Sparse(SparseMemory),
+}
+
+impl Storage {
+    /// The addresses that hold the program's words, in order: every address of dense storage,
+    /// but only the set ones of sparse storage, so sweeping memory stays proportional to its size.
+    pub fn addresses(&self) -> Box<dyn Iterator<Item=usize> + '_> {
+        match self {
+            Storage::Dense(data) => Box::new(0..data.len()),
+            Storage::Sparse(data) => Box::new(data.cells().into_iter().map(|(address, _)| address)),
+        }
+    }
+}
+
+impl Memory for Storage {
+    fn len(&self) -> usize {
+        match self {
+            Storage::Dense(data) => Memory::len(data),
+            Storage::Sparse(data) => data.len(),
+        }
+    }
+
+    fn get(&self, address: usize) -> &i64 {
+        match self {
+            Storage::Dense(data) => Memory::get(data, address),
+            Storage::Sparse(data) => data.get(address),
+        }
+    }
+
+    fn get_mut(&mut self, address: usize) -> &mut i64 {
+        if let Storage::Dense(data) = self {
+            if address >= Vec::len(data) + SPARSE_GAP {
+                *self = Storage::Sparse(std::mem::take(data).into());
+            }
+        }
+
+        match self {
+            Storage::Dense(data) => Memory::get_mut(data, address),
+            Storage::Sparse(data) => data.get_mut(address),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparse() {
+        let mut mem = SparseMemory::from(vec![1, 0, 3]);
+        assert_eq!(mem.len(), 3);
+        assert_eq!(*mem.get(1), 0);
+        assert_eq!(*mem.get(2), 3);
+
+        *mem.get_mut(5_000_000_000) = 7;
+        assert_eq!(mem.len(), 5_000_000_001);
+        assert_eq!(*mem.get(5_000_000_000), 7);
+        assert_eq!(mem.cells(), vec![(0, 1), (2, 3), (5_000_000_000, 7)]);
+    }
+
+    #[test]
+    fn test_storage_switch() {
+        let mut mem = Storage::Dense(vec![1, 2]);
+        *mem.get_mut(100) = 3;
+        assert!(matches!(mem, Storage::Dense(ref d) if d.len() == 101));
+
+        *mem.get_mut(3_000_000_000) = 4;
+        assert!(matches!(mem, Storage::Sparse(_)));
+        assert_eq!(mem.len(), 3_000_000_001);
+        assert_eq!((*mem.get(0), *mem.get(100), *mem.get(3_000_000_000)), (1, 3, 4));
+        assert_eq!(mem.addresses().collect::<Vec<_>>(), vec![0, 1, 100, 3_000_000_000]);
+        assert_eq!(Storage::Dense(vec![0, 5]).addresses().collect::<Vec<_>>(), vec![0, 1]);
+    }
+}
@@ -0,0 +1,244 @@
+use super::{IntCodeComputer, IntCodeError, State};
+use std::collections::VecDeque;
+
+/// The address whose packets are captured by the NAT instead of being delivered.
+pub const NAT_ADDRESS: i64 = 255;
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Packet {
+    pub dest: i64,
+    pub x: i64,
+    pub y: i64,
+}
+
+/// Something observable that happened during a round of the network.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Event {
+    /// A machine sent a packet. Packets to unknown addresses are dropped after being reported.
+    Sent { from: usize, packet: Packet },
+    /// The network was idle, so the NAT resent its last packet to address 0.
+    NatWake(Packet),
+}
+
+struct Node {
+    comp: IntCodeComputer,
+    queue: VecDeque<(i64, i64)>,
+    pending: Vec<i64>,
+    idle: bool,
+}
+
+/// A network of copies of one program, each booted with its address as first input. Machines
+/// are run round-robin: each turn a machine runs until it asks for input with an empty packet
+/// queue twice, receiving -1 the first time, so the schedule is fully deterministic.
+pub struct Network {
+    nodes: Vec<Node>,
+    nat: Option<Packet>,
+}
+
+impl Network {
+    pub fn new(program: &IntCodeComputer, size: usize) -> Self {
+        let nodes = (0..size)
+            .map(|address| {
+                let mut comp = program.clone();
+                comp.push_input(address as i64);
+                Node { comp, queue: VecDeque::new(), pending: Vec::new(), idle: false }
+            })
+            .collect();
+
+        Self { nodes, nat: None }
+    }
+
+    /// The last packet sent to the NAT, if any.
+    pub fn nat(&self) -> Option<Packet> { self.nat }
+
+    pub fn computer(&self, address: usize) -> &IntCodeComputer { &self.nodes[address].comp }
+
+    /// Reports whether every machine that has not halted is idle with an empty queue.
+    pub fn is_idle(&self) -> bool {
+        self.nodes.iter().all(|n| n.comp.is_halted() || (n.idle && n.queue.is_empty()))
+    }
+
+    /// Runs one round: a turn for every machine in address order, then a NAT wake-up if the
+    /// whole network went idle and address 0 is still running.
+    pub fn round(&mut self) -> Result<Vec<Event>, IntCodeError> {
+        let mut events = Vec::new();
+
+        for from in 0..self.nodes.len() {
+            for packet in self.turn(from)? {
+                events.push(Event::Sent { from, packet });
+                self.deliver(packet);
+            }
+        }
+
+        if self.is_idle() && self.can_wake() {
+            if let Some(packet) = self.nat {
+                let packet = Packet { dest: 0, ..packet };
+                events.push(Event::NatWake(packet));
+                self.deliver(packet);
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// Runs rounds until `stop` accepts an event, returning that event. Returns `None` if the
+    /// network stalls: idle with nothing for the NAT to send or no running machine at address 0
+    /// to send it to, or every machine halted.
+    pub fn run_until<F: FnMut(&Event) -> bool>(&mut self, mut stop: F) -> Result<Option<Event>, IntCodeError> {
+        loop {
+            let events = self.round()?;
+            if let Some(event) = events.into_iter().find(|e| stop(e)) {
+                return Ok(Some(event));
+            }
+
+            let halted = self.nodes.iter().all(|n| n.comp.is_halted());
+            if halted || (self.is_idle() && !self.can_wake()) {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn can_wake(&self) -> bool {
+        self.nat.is_some() && self.nodes.first().is_some_and(|n| !n.comp.is_halted())
+    }
+
+    fn turn(&mut self, address: usize) -> Result<Vec<Packet>, IntCodeError> {
+        let node = &mut self.nodes[address];
+        let mut sent = Vec::new();
+        let mut polled = false;
+        let mut idle = true;
+
+        loop {
+            match node.comp.resume()? {
+                State::Output(value) => {
+                    idle = false;
+                    node.pending.push(value);
+                    if let [dest, x, y] = node.pending[..] {
+                        sent.push(Packet { dest, x, y });
+                        node.pending.clear();
+                    }
+                }
+                State::NeedsInput => {
+                    if let Some((x, y)) = node.queue.pop_front() {
+                        idle = false;
+                        node.comp.push_input(x);
+                        node.comp.push_input(y);
+                    } else if polled {
+                        break;
+                    } else {
+                        polled = true;
+                        node.comp.push_input(-1);
+                    }
+                }
+                State::Halted => break,
+            }
+        }
+
+        node.idle = idle;
+        Ok(sent)
+    }
+
+    fn deliver(&mut self, packet: Packet) {
+        if packet.dest == NAT_ADDRESS {
+            self.nat = Some(packet);
+        } else if let Some(node) = self.nodes.get_mut(packet.dest as usize).filter(|_| packet.dest >= 0) {
+            node.queue.push_back((packet.x, packet.y));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::assembler::assemble;
+
+    // Node 0 starts by sending (0, 0) to node 1. Every node passes each packet it receives on to
+    // the next address with y incremented, and the last node sends it to the NAT instead.
+    fn relay(size: i64) -> IntCodeComputer {
+        let program = assemble(&format!("
+                    IN -> [addr]
+                    JT [addr] #loop
+                    OUT #1
+                    OUT #0
+                    OUT #0
+            loop:   IN -> [x]
+                    EQ [x] #-1 -> [tmp]
+                    JT [tmp] #loop
+                    IN -> [y]
+                    ADD [y] #1 -> [y]
+                    ADD [addr] #1 -> [next]
+                    LT [next] #{} -> [tmp]
+                    JT [tmp] #send
+                    ADD #255 #0 -> [next]
+            send:   OUT [next]
+                    OUT [x]
+                    OUT [y]
+                    JT #1 #loop
+            addr:   .data 0
+            x:      .data 0
+            y:      .data 0
+            next:   .data 0
+            tmp:    .data 0
+        ", size)).unwrap();
+        IntCodeComputer::new(program, None)
+    }
+
+    #[test]
+    fn test_round() {
+        let mut net = Network::new(&relay(3), 3);
+
+        assert_eq!(net.round(), Ok(vec![
+            Event::Sent { from: 0, packet: Packet { dest: 1, x: 0, y: 0 } },
+            Event::Sent { from: 1, packet: Packet { dest: 2, x: 0, y: 1 } },
+            Event::Sent { from: 2, packet: Packet { dest: 255, x: 0, y: 2 } },
+        ]));
+        assert_eq!(net.nat(), Some(Packet { dest: 255, x: 0, y: 2 }));
+        assert!(!net.is_idle());
+
+        assert_eq!(net.round(), Ok(vec![Event::NatWake(Packet { dest: 0, x: 0, y: 2 })]));
+        assert_eq!(net.round(), Ok(vec![
+            Event::Sent { from: 0, packet: Packet { dest: 1, x: 0, y: 3 } },
+            Event::Sent { from: 1, packet: Packet { dest: 2, x: 0, y: 4 } },
+            Event::Sent { from: 2, packet: Packet { dest: 255, x: 0, y: 5 } },
+        ]));
+    }
+
+    #[test]
+    fn test_run_until() {
+        let mut net = Network::new(&relay(4), 4);
+        let event = net.run_until(|e| matches!(e, Event::NatWake(p) if p.y > 10));
+        assert_eq!(event, Ok(Some(Event::NatWake(Packet { dest: 0, x: 0, y: 11 }))));
+    }
+
+    #[test]
+    fn test_stalled() {
+        // The lone node's opening packet is addressed to a machine that does not exist.
+        let mut net = Network::new(&relay(1), 1);
+        assert_eq!(net.run_until(|_| false), Ok(None));
+        assert!(net.is_idle());
+        assert_eq!(net.nat(), None);
+    }
+
+    #[test]
+    fn test_halted_address_0() {
+        // Address 0 halts at once; address 1 sends one packet to the NAT and then only polls.
+        let program = assemble("
+                    IN -> [addr]
+                    JT [addr] #send
+                    HLT
+            send:   OUT #255
+                    OUT [addr]
+                    OUT #7
+            poll:   IN -> [x]
+                    JT #1 #poll
+            addr:   .data 0
+            x:      .data 0
+        ").unwrap();
+        let mut net = Network::new(&IntCodeComputer::new(program, None), 2);
+
+        assert_eq!(net.run_until(|_| false), Ok(None));
+        assert!(net.computer(0).is_halted());
+        assert!(net.is_idle());
+        assert_eq!(net.nat(), Some(Packet { dest: 255, x: 1, y: 7 }));
+    }
+}
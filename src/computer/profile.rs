@@ -0,0 +1,147 @@
+use super::{IntCodeComputer, Op};
+use std::collections::BTreeMap;
+
+/// Execution counts gathered while profiling: total steps, steps per operation, steps per
+/// instruction address, and how often each backward jump (a loop) was taken.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Profile {
+    pub steps: u64,
+    pub by_op: BTreeMap<&'static str, u64>,
+    pub by_idx: BTreeMap<usize, u64>,
+    pub loops: BTreeMap<(usize, usize), u64>,
+}
+
+/// A loop spanning `start..=end`, found from a backward jump at `end`.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct HotLoop {
+    pub start: usize,
+    pub end: usize,
+    pub iterations: u64,
+    pub steps: u64,
+}
+
+impl Profile {
+    pub(super) fn record(&mut self, idx: usize, op: Op, next: usize) {
+        self.steps += 1;
+        *self.by_op.entry(op.mnemonic()).or_default() += 1;
+        *self.by_idx.entry(idx).or_default() += 1;
+
+        if next <= idx && op != Op::Halt {
+            *self.loops.entry((next, idx)).or_default() += 1;
+        }
+    }
+
+    /// Returns the share of all steps spent in `steps`, as a percentage.
+    pub fn share(&self, steps: u64) -> f64 {
+        if self.steps == 0 { 0.0 } else { 100.0 * steps as f64 / self.steps as f64 }
+    }
+
+    /// Lists loops by the number of steps executed inside them, hottest first.
+    pub fn hot_loops(&self) -> Vec<HotLoop> {
+        let mut loops: Vec<HotLoop> = self.loops.iter()
+            .map(|(&(start, end), &iterations)| HotLoop {
+                start,
+                end,
+                iterations,
+                steps: self.by_idx.range(start..=end).map(|(_, &count)| count).sum(),
+            })
+            .collect();
+        loops.sort_by(|a, b| b.steps.cmp(&a.steps).then(a.start.cmp(&b.start)));
+        loops
+    }
+
+    /// Lists instruction addresses by execution count, hottest first.
+    pub fn hot_addresses(&self) -> Vec<(usize, u64)> {
+        let mut addresses: Vec<(usize, u64)> = self.by_idx.iter().map(|(&idx, &count)| (idx, count)).collect();
+        addresses.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        addresses
+    }
+
+    /// Renders a summary listing the `top` hottest entries in each section.
+    pub fn report(&self, top: usize) -> String {
+        let mut out = format!("total steps: {}\n\nby op:\n", self.steps);
+
+        let mut ops: Vec<(&str, u64)> = self.by_op.iter().map(|(&op, &count)| (op, count)).collect();
+        ops.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        for (op, count) in ops {
+            out.push_str(&format!("  {:<4} {:>10} {:>6.2}%\n", op, count, self.share(count)));
+        }
+
+        out.push_str("\nhot addresses:\n");
+        for (idx, count) in self.hot_addresses().into_iter().take(top) {
+            out.push_str(&format!("  {:>5} {:>10} {:>6.2}%\n", idx, count, self.share(count)));
+        }
+
+        out.push_str("\nhot loops:\n");
+        for l in self.hot_loops().into_iter().take(top) {
+            out.push_str(&format!("  {:>5}..={:<5} {:>10} iterations {:>10} steps {:>6.2}%\n",
+                                  l.start, l.end, l.iterations, l.steps, self.share(l.steps)));
+        }
+
+        out
+    }
+}
+
+impl IntCodeComputer {
+    /// Starts counting executed instructions from here on.
+    pub fn enable_profile(&mut self) {
+        if self.profile.is_none() {
+            self.profile = Some(Profile::default());
+        }
+    }
+
+    /// Stops profiling and returns the counts gathered so far.
+    pub fn disable_profile(&mut self) -> Profile { self.profile.take().unwrap_or_default() }
+
+    pub fn profile(&self) -> Option<&Profile> { self.profile.as_ref() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::countdown;
+
+    #[test]
+    fn test_profile() {
+        let mut comp = countdown();
+        comp.enable_profile();
+        comp.run();
+
+        let profile = comp.disable_profile();
+        assert_eq!(profile.steps, 10);
+        assert_eq!(profile.by_op.get("OUT"), Some(&3));
+        assert_eq!(profile.by_op.get("HLT"), Some(&1));
+        assert_eq!(profile.by_idx.get(&6), Some(&3));
+        assert_eq!(profile.hot_loops(), vec![HotLoop { start: 0, end: 6, iterations: 2, steps: 9 }]);
+        assert_eq!(profile.hot_addresses()[0], (0, 3));
+        assert_eq!(profile.share(5), 50.0);
+
+        assert_eq!(profile.report(1), "\
+total steps: 10
+
+by op:
+  ADD           3  30.00%
+  JT            3  30.00%
+  OUT           3  30.00%
+  HLT           1  10.00%
+
+hot addresses:
+      0          3  30.00%
+
+hot loops:
+      0..=6              2 iterations          9 steps  90.00%
+");
+    }
+
+    #[test]
+    fn test_profile_diagnostics() {
+        let mut comp = IntCodeComputer::from_input_file("dec05.txt", vec![5]);
+        comp.enable_profile();
+        assert_eq!(comp.try_run().map(|out| out.last().copied()), Ok(Some(5_893_654)));
+
+        let profile = comp.profile().expect("profiling enabled");
+        assert_eq!(profile.by_op.values().sum::<u64>(), profile.steps);
+        assert_eq!(profile.by_idx.values().sum::<u64>(), profile.steps);
+        assert_eq!(profile.by_op.get("HLT"), Some(&1));
+    }
+}
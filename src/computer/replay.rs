@@ -0,0 +1,325 @@
+use super::{IntCodeComputer, IntCodeError, State};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+const MAGIC: &str = "intcode-session";
+const VERSION: u32 = 1;
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum IoEvent {
+    Input(i64),
+    Output(i64),
+}
+
+/// A failure while loading a recording.
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(io::Error),
+    UnsupportedVersion(u32),
+    Malformed { line: usize, message: String },
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingError::Io(err) => write!(f, "recording io error: {}", err),
+            RecordingError::UnsupportedVersion(v) => write!(f, "unsupported recording version {}", v),
+            RecordingError::Malformed { line, message } => write!(f, "malformed recording on line {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for RecordingError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RecordingError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RecordingError {
+    fn from(err: io::Error) -> Self { RecordingError::Io(err) }
+}
+
+/// The inputs consumed and outputs produced by a session, in the order they happened. Saved as
+/// a header line followed by one `in <value>` or `out <value>` line per event.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Recording(pub Vec<IoEvent>);
+
+impl Recording {
+    pub fn save<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "{} {}", MAGIC, VERSION)?;
+        for event in &self.0 {
+            match event {
+                IoEvent::Input(value) => writeln!(w, "in {}", value)?,
+                IoEvent::Output(value) => writeln!(w, "out {}", value)?,
+            }
+        }
+        w.flush()
+    }
+
+    pub fn load<R: BufRead>(r: R) -> Result<Self, RecordingError> {
+        let mut lines = r.lines();
+        let header = lines.next()
+            .unwrap_or_else(|| Err(io::Error::new(io::ErrorKind::UnexpectedEof, "empty recording")))?;
+        let version = match header.split_once(' ') {
+            Some((MAGIC, version)) => version.trim().parse::<u32>().map_err(|_| RecordingError::Malformed {
+                line: 1,
+                message: format!("invalid value {:?}", version),
+            })?,
+            _ => return Err(RecordingError::Malformed { line: 1, message: format!("expected {:?}", MAGIC) }),
+        };
+        if version == 0 || version > VERSION {
+            return Err(RecordingError::UnsupportedVersion(version));
+        }
+
+        let mut events = Vec::new();
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            let mut parts = line.split_whitespace();
+            let (kind, value) = (parts.next(), parts.next().map(str::parse::<i64>));
+            let event = match (kind, value, parts.next()) {
+                (Some("in"), Some(Ok(value)), None) => IoEvent::Input(value),
+                (Some("out"), Some(Ok(value)), None) => IoEvent::Output(value),
+                (None, _, _) => continue,
+                _ => return Err(RecordingError::Malformed {
+                    line: i + 2,
+                    message: "expected \"in <value>\" or \"out <value>\"".to_string(),
+                }),
+            };
+            events.push(event);
+        }
+
+        Ok(Recording(events))
+    }
+
+    pub fn save_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.save(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Self, RecordingError> {
+        Self::load(BufReader::new(File::open(path)?))
+    }
+}
+
+/// Drives a computer like `resume`, recording each input at the moment the program consumes it
+/// and each output as it is produced. Inputs already queued on the computer are taken over so
+/// they are recorded too. Any attached source or sink is detached, since events it handled
+/// would bypass the recording.
+pub struct Recorder {
+    comp: IntCodeComputer,
+    pending: VecDeque<i64>,
+    recording: Recording,
+}
+
+impl Recorder {
+    pub fn new(mut comp: IntCodeComputer) -> Self {
+        let pending = std::mem::take(&mut comp.input);
+        comp.detach_input();
+        comp.detach_output();
+        Self { comp, pending, recording: Recording::default() }
+    }
+
+    pub fn push_input(&mut self, value: i64) { self.pending.push_back(value); }
+
+    pub fn resume(&mut self) -> Result<State, IntCodeError> {
+        loop {
+            match self.comp.resume()? {
+                State::Output(value) => {
+                    self.recording.0.push(IoEvent::Output(value));
+                    return Ok(State::Output(value));
+                }
+                State::NeedsInput => match self.pending.pop_front() {
+                    Some(value) => {
+                        self.recording.0.push(IoEvent::Input(value));
+                        self.comp.push_input(value);
+                    }
+                    None => return Ok(State::NeedsInput),
+                },
+                State::Halted => return Ok(State::Halted),
+            }
+        }
+    }
+
+    pub fn resume_with(&mut self, value: i64) -> Result<State, IntCodeError> {
+        self.push_input(value);
+        self.resume()
+    }
+
+    pub fn recording(&self) -> &Recording { &self.recording }
+
+    pub fn computer(&self) -> &IntCodeComputer { &self.comp }
+
+    pub fn into_recording(self) -> Recording { self.recording }
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum ReplayError {
+    Fault(IntCodeError),
+    /// Event `index` of the recording was `expected` (`None` past its end), but the program
+    /// reached `actual` instead.
+    Diverged { index: usize, expected: Option<IoEvent>, actual: State },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Fault(err) => write!(f, "replay faulted: {}", err),
+            ReplayError::Diverged { index, expected, actual } =>
+                write!(f, "replay diverged at event {}: expected {:?}, got {:?}", index, expected, actual),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+impl From<IntCodeError> for ReplayError {
+    fn from(err: IntCodeError) -> Self { ReplayError::Fault(err) }
+}
+
+/// Feeds the recorded inputs to `comp` as it asks for them and checks every output against
+/// the recording. Succeeds with the final state once the recording is used up and the program
+/// has halted or is waiting for more input, as it was when recording stopped. Any source or
+/// sink attached to `comp` is detached first.
+pub fn replay(mut comp: IntCodeComputer, recording: &Recording) -> Result<State, ReplayError> {
+    comp.detach_input();
+    comp.detach_output();
+    let mut events = recording.0.iter().copied().enumerate().peekable();
+
+    loop {
+        let expected = events.peek().map(|&(_, event)| event);
+        let index = events.peek().map_or(recording.0.len(), |&(i, _)| i);
+
+        match (comp.resume()?, expected) {
+            (State::Output(value), Some(IoEvent::Output(want))) if value == want => {
+                events.next();
+            }
+            (State::NeedsInput, Some(IoEvent::Input(value))) => {
+                events.next();
+                comp.push_input(value);
+            }
+            (state @ State::NeedsInput, None) | (state @ State::Halted, None) => return Ok(state),
+            (actual, _) => return Err(ReplayError::Diverged { index, expected, actual }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::assembler::assemble;
+    use crate::computer::io::IterInput;
+    use std::sync::mpsc::channel;
+
+    // Outputs the running total of its inputs until a zero is read.
+    fn adder(input: Vec<i64>) -> IntCodeComputer {
+        let program = assemble("
+            loop:   IN -> [x]
+                    JF [x] #done
+                    ADD [x] [sum] -> [sum]
+                    OUT [sum]
+                    JT #1 #loop
+            done:   HLT
+            x:      .data 0
+            sum:    .data 0
+        ").unwrap();
+        IntCodeComputer::new(program, input)
+    }
+
+    fn session() -> Recording {
+        let mut rec = Recorder::new(adder(vec![5]));
+        assert_eq!(rec.resume(), Ok(State::Output(5)));
+        assert_eq!(rec.resume(), Ok(State::NeedsInput));
+        rec.push_input(3);
+        rec.push_input(4);
+        assert_eq!(rec.resume(), Ok(State::Output(8)));
+        assert_eq!(rec.resume(), Ok(State::Output(12)));
+        assert_eq!(rec.resume(), Ok(State::NeedsInput));
+        rec.into_recording()
+    }
+
+    #[test]
+    fn test_record() {
+        use IoEvent::*;
+        assert_eq!(session(), Recording(vec![Input(5), Output(5), Input(3), Output(8), Input(4), Output(12)]));
+    }
+
+    #[test]
+    fn test_attached_ports() {
+        let (out_tx, out_rx) = channel();
+        let mut comp = adder(vec![5]);
+        comp.attach_input(IterInput(vec![1, 2].into_iter()));
+        comp.attach_output(out_tx.clone());
+        let mut rec = Recorder::new(comp);
+        assert_eq!(rec.resume(), Ok(State::Output(5)));
+        assert_eq!(rec.resume(), Ok(State::NeedsInput));
+        assert_eq!(rec.recording(), &Recording(vec![IoEvent::Input(5), IoEvent::Output(5)]));
+
+        let mut comp = adder(vec![]);
+        comp.attach_input(IterInput(vec![1, 2].into_iter()));
+        comp.attach_output(out_tx);
+        assert_eq!(replay(comp, &rec.into_recording()), Ok(State::NeedsInput));
+        assert_eq!(out_rx.try_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let mut buf = Vec::new();
+        session().save(&mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf.clone()).unwrap(), "\
+intcode-session 1
+in 5
+out 5
+in 3
+out 8
+in 4
+out 12
+");
+        assert_eq!(Recording::load(&buf[..]).unwrap(), session());
+
+        let path = std::env::temp_dir().join(format!("intcode-session-{}.txt", std::process::id()));
+        session().save_file(&path).unwrap();
+        let loaded = Recording::load_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), session());
+
+        let load = |s: &str| Recording::load(s.as_bytes()).unwrap_err().to_string();
+        assert_eq!(load("intcode-session 1\nin x\n"),
+                   "malformed recording on line 2: expected \"in <value>\" or \"out <value>\"");
+        assert_eq!(load("intcode-session 9\n"), "unsupported recording version 9");
+        assert_eq!(load("intcode-snapshot 1\n"), "malformed recording on line 1: expected \"intcode-session\"");
+        assert_eq!(load(""), "recording io error: empty recording");
+    }
+
+    #[test]
+    fn test_replay() {
+        let recording = session();
+        let fresh = adder(vec![]);
+        assert_eq!(replay(fresh.clone(), &recording), Ok(State::NeedsInput));
+
+        let mut finished = recording.clone();
+        finished.0.push(IoEvent::Input(0));
+        assert_eq!(replay(fresh.clone(), &finished), Ok(State::Halted));
+
+        let mut tampered = recording.clone();
+        tampered.0[3] = IoEvent::Output(9);
+        assert_eq!(replay(fresh.clone(), &tampered), Err(ReplayError::Diverged {
+            index: 3,
+            expected: Some(IoEvent::Output(9)),
+            actual: State::Output(8),
+        }));
+
+        let mut extra = recording;
+        extra.0.push(IoEvent::Output(99));
+        assert_eq!(replay(fresh, &extra), Err(ReplayError::Diverged {
+            index: 6,
+            expected: Some(IoEvent::Output(99)),
+            actual: State::NeedsInput,
+        }));
+    }
+}
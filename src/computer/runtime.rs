@@ -0,0 +1,197 @@
+use super::io::InputSource;
+use super::{IntCodeComputer, IntCodeError};
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How often a machine blocked on input checks whether it has been cancelled.
+const POLL: Duration = Duration::from_millis(10);
+
+/// Why a machine's thread stopped.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Exit {
+    Halted,
+    Cancelled,
+    InputClosed,
+    Faulted(IntCodeError),
+}
+
+/// The result of a machine thread, including the computer so its final memory can be read.
+pub struct Finished {
+    pub exit: Exit,
+    pub computer: IntCodeComputer,
+    pub last_output: Option<i64>,
+}
+
+/// A computer running on its own thread, reading from and writing to channels.
+pub struct Machine {
+    cancel: Arc<AtomicBool>,
+    handle: JoinHandle<Finished>,
+}
+
+impl Machine {
+    /// Starts `comp` on a new thread. Once its own input queue is empty it blocks on `input`,
+    /// and every output is sent to `output`. Outputs sent after the receiver hangs up are dropped.
+    /// Any source or sink already attached to `comp` is replaced, and the finished computer
+    /// comes back with none attached.
+    pub fn spawn(mut comp: IntCodeComputer, input: Receiver<i64>, output: Sender<i64>) -> Self {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancel);
+
+        let handle = thread::spawn(move || {
+            comp.attach_input(CancellableInput { input, cancel: Arc::clone(&flag) });
+            comp.attach_output(output);
+
+            let mut last_output = None;
+            let exit = run(&mut comp, &flag, &mut last_output);
+            comp.detach_input();
+            comp.detach_output();
+            Finished { exit, computer: comp, last_output }
+        });
+
+        Self { cancel, handle }
+    }
+
+    /// Asks the machine to stop at its next instruction or input poll.
+    pub fn cancel(&self) { self.cancel.store(true, Ordering::Relaxed); }
+
+    pub fn is_finished(&self) -> bool { self.handle.is_finished() }
+
+    /// Waits for the machine's thread to stop. A panic on that thread is propagated.
+    pub fn join(self) -> Finished {
+        self.handle.join().unwrap_or_else(|err| panic::resume_unwind(err))
+    }
+}
+
+/// Blocks on a channel for input, giving up once the machine is cancelled or every sender has
+/// hung up.
+struct CancellableInput {
+    input: Receiver<i64>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl InputSource for CancellableInput {
+    fn next_input(&mut self) -> Option<i64> {
+        while !self.cancel.load(Ordering::Relaxed) {
+            match self.input.recv_timeout(POLL) {
+                Ok(value) => return Some(value),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+        None
+    }
+}
+
+/// Steps `comp`, whose input and output are attached channels, until it stops.
+fn run(comp: &mut IntCodeComputer, cancel: &AtomicBool, last_output: &mut Option<i64>) -> Exit {
+    while !comp.is_halted() {
+        if cancel.load(Ordering::Relaxed) {
+            return Exit::Cancelled;
+        }
+
+        match comp.try_step() {
+            Ok(Some(value)) => *last_output = Some(value),
+            Ok(None) => {}
+            Err(IntCodeError::InputExhausted { .. }) if cancel.load(Ordering::Relaxed) => return Exit::Cancelled,
+            Err(IntCodeError::InputExhausted { .. }) => return Exit::InputClosed,
+            Err(err) => return Exit::Faulted(err),
+        }
+    }
+
+    Exit::Halted
+}
+
+/// A group of machines that can be cancelled and joined together.
+#[derive(Default)]
+pub struct Runtime {
+    machines: Vec<Machine>,
+}
+
+impl Runtime {
+    pub fn new() -> Self { Self::default() }
+
+    /// Starts a machine in the group, returning its index in the results of `join_all`.
+    pub fn spawn(&mut self, comp: IntCodeComputer, input: Receiver<i64>, output: Sender<i64>) -> usize {
+        self.machines.push(Machine::spawn(comp, input, output));
+        self.machines.len() - 1
+    }
+
+    pub fn cancel_all(&self) { self.machines.iter().for_each(Machine::cancel); }
+
+    /// Waits for every machine, returning their results in spawn order.
+    pub fn join_all(self) -> Vec<Finished> { self.machines.into_iter().map(Machine::join).collect() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_feedback_ring() {
+        let program = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28,
+            1005, 28, 6, 99, 0, 0, 5,
+        ];
+        let phases = [9, 8, 7, 6, 5];
+
+        let (senders, receivers): (Vec<_>, Vec<_>) = phases.iter().map(|_| channel()).unzip();
+        senders[0].send(0).unwrap();
+
+        let mut runtime = Runtime::new();
+        for (i, (&phase, input)) in phases.iter().zip(receivers).enumerate() {
+            let output = senders[(i + 1) % phases.len()].clone();
+            runtime.spawn(IntCodeComputer::new(program.clone(), vec![phase]), input, output);
+        }
+        drop(senders);
+
+        let results = runtime.join_all();
+        assert!(results.iter().all(|r| r.exit == Exit::Halted));
+        assert_eq!(results[4].last_output, Some(139_629_729));
+        assert_eq!(results[4].computer[28], 0);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let (_tx, rx) = channel();
+        let (out, _out_rx) = channel();
+        let spinning = Machine::spawn(IntCodeComputer::new(vec![1105, 1, 0], None), rx, out);
+
+        let (_tx, rx) = channel();
+        let (out, _out_rx) = channel();
+        let blocked = Machine::spawn(IntCodeComputer::new(vec![3, 0, 99], None), rx, out);
+
+        spinning.cancel();
+        blocked.cancel();
+        assert_eq!(spinning.join().exit, Exit::Cancelled);
+
+        let finished = blocked.join();
+        assert_eq!(finished.exit, Exit::Cancelled);
+        assert!(finished.computer.awaiting_input());
+    }
+
+    #[test]
+    fn test_input_closed_and_fault() {
+        let mut runtime = Runtime::new();
+
+        let (tx, rx) = channel();
+        let (out, out_rx) = channel();
+        runtime.spawn(IntCodeComputer::new(vec![3, 10, 4, 10, 1105, 1, 0], None), rx, out);
+        tx.send(42).unwrap();
+        assert_eq!(out_rx.recv(), Ok(42));
+        drop(tx);
+
+        let (_tx, rx) = channel();
+        let (out, _out_rx) = channel();
+        runtime.spawn(IntCodeComputer::new(vec![42], None), rx, out);
+
+        let results = runtime.join_all();
+        assert_eq!(results[0].exit, Exit::InputClosed);
+        assert_eq!(results[0].computer[10], 42);
+        assert_eq!(results[1].exit, Exit::Faulted(IntCodeError::UnknownOpcode { idx: 0, opcode: 42 }));
+    }
+}
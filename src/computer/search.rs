@@ -0,0 +1,115 @@
+use super::IntCodeComputer;
+use std::sync::Mutex;
+use std::thread;
+
+/// One point in a search: memory patches applied to the base program and the inputs it is fed.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Candidate {
+    pub patches: Vec<(usize, i64)>,
+    pub input: Vec<i64>,
+}
+
+impl Candidate {
+    pub fn patches(patches: Vec<(usize, i64)>) -> Self { Self { patches, input: Vec::new() } }
+
+    pub fn input(input: Vec<i64>) -> Self { Self { patches: Vec::new(), input } }
+
+    fn run(&self, base: &IntCodeComputer) -> Option<(IntCodeComputer, Vec<i64>)> {
+        let mut comp = base.clone();
+        for &(address, value) in &self.patches {
+            comp[address] = value;
+        }
+        for &value in &self.input {
+            comp.push_input(value);
+        }
+        let outputs = comp.try_run().ok()?;
+        Some((comp, outputs))
+    }
+}
+
+/// A candidate whose run satisfied the predicate, along with the halted computer and its outputs.
+pub struct Found {
+    pub index: usize,
+    pub candidate: Candidate,
+    pub computer: IntCodeComputer,
+    pub outputs: Vec<i64>,
+}
+
+/// Runs `search_with_threads` on every available core.
+pub fn search<C, P>(base: &IntCodeComputer, candidates: C, predicate: P) -> Option<Found>
+    where C: IntoIterator<Item=Candidate>, C::IntoIter: Send, P: Fn(&IntCodeComputer, &[i64]) -> bool + Sync
+{
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    search_with_threads(base, threads, candidates, predicate)
+}
+
+/// Runs each candidate to completion across `threads` workers and returns the earliest one, in
+/// iteration order, whose final memory and outputs satisfy `predicate`. Workers stop taking new
+/// candidates once a match is found. Candidates that fault or block on input never match.
+pub fn search_with_threads<C, P>(base: &IntCodeComputer, threads: usize, candidates: C, predicate: P) -> Option<Found>
+    where C: IntoIterator<Item=Candidate>, C::IntoIter: Send, P: Fn(&IntCodeComputer, &[i64]) -> bool + Sync
+{
+    let queue = Mutex::new(candidates.into_iter().enumerate());
+    let best: Mutex<Option<Found>> = Mutex::new(None);
+
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| loop {
+                let (index, candidate) = match queue.lock().expect("search queue poisoned").next() {
+                    Some(next) => next,
+                    None => return,
+                };
+
+                if best.lock().expect("search result poisoned").as_ref().is_some_and(|f| f.index < index) {
+                    return;
+                }
+
+                if let Some((computer, outputs)) = candidate.run(base) {
+                    if predicate(&computer, &outputs) {
+                        let mut best = best.lock().expect("search result poisoned");
+                        if best.as_ref().map_or(true, |f| index < f.index) {
+                            *best = Some(Found { index, candidate, computer, outputs });
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    best.into_inner().expect("search result poisoned")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_inputs() {
+        // Outputs 1 if the input equals 8, otherwise 0.
+        let base = IntCodeComputer::new(vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8], None);
+        let found = search_with_threads(&base, 4, (0..100).map(|i| Candidate::input(vec![i])), |_, out| out == [1])
+            .expect("should find the matching input");
+
+        assert_eq!(found.index, 8);
+        assert_eq!(found.candidate, Candidate::input(vec![8]));
+        assert_eq!(found.computer[9], 1);
+    }
+
+    #[test]
+    fn test_search_earliest_match() {
+        // Outputs the sum of [9] and [10].
+        let base = IntCodeComputer::new(vec![1, 9, 10, 11, 4, 11, 99, 0, 0, 0, 0, 0], None);
+        let candidates = (0..50).flat_map(|a| (0..50).map(move |b| Candidate::patches(vec![(9, a), (10, b)])));
+        let found = search(&base, candidates, |comp, _| comp[11] == 42).expect("should find a sum");
+
+        assert_eq!(found.candidate.patches, vec![(9, 0), (10, 42)]);
+        assert_eq!(found.outputs, vec![42]);
+    }
+
+    #[test]
+    fn test_search_no_match() {
+        let base = IntCodeComputer::new(vec![3, 0, 99], None);
+        let candidates = vec![Candidate::default(), Candidate::patches(vec![(0, 42)])];
+        assert!(search(&base, candidates, |_, _| true).is_none());
+    }
+}
@@ -0,0 +1,297 @@
+use super::memory::{Memory, SparseMemory, Storage};
+use super::{Arithmetic, IntCodeComputer};
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+const MAGIC: &str = "intcode-snapshot";
+const VERSION: u32 = 3;
+
+/// A failure while loading a snapshot.
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    UnsupportedVersion(u32),
+    Malformed { line: usize, message: String },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(err) => write!(f, "snapshot io error: {}", err),
+            SnapshotError::UnsupportedVersion(v) => write!(f, "unsupported snapshot version {}", v),
+            SnapshotError::Malformed { line, message } => write!(f, "malformed snapshot on line {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SnapshotError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> Self { SnapshotError::Io(err) }
+}
+
+impl IntCodeComputer {
+    /// Writes the full machine state as a line-oriented text snapshot:
+    ///
+    /// ```text
+    /// intcode-snapshot 3
+    /// idx 6
+    /// relative_base 0
+    /// halted 0
+    /// arithmetic checked
+    /// input 5,8
+    /// memory 3,9,8,9,10,9,4,9,99,-1,8
+    /// ```
+    ///
+    /// Sparse memory is written as its length followed by the set addresses instead, such as
+    /// `sparse_memory 5000000001 0=1101,5000000000=5`. Version 1 snapshots, which only have the
+    /// dense form, and version 2 snapshots, which lack the `arithmetic` line and load as
+    /// `checked`, can still be read. Tracing and profiling state is not included.
+    pub fn save_snapshot<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "{} {}", MAGIC, VERSION)?;
+        writeln!(w, "idx {}", self.idx)?;
+        writeln!(w, "relative_base {}", self.relative_base)?;
+        writeln!(w, "halted {}", self.halted as u8)?;
+        writeln!(w, "arithmetic {}", arithmetic_name(self.arithmetic))?;
+        writeln!(w, "input {}", join(&self.input))?;
+        match &self.data {
+            Storage::Dense(data) => writeln!(w, "memory {}", join(data))?,
+            Storage::Sparse(data) => {
+                let cells: Vec<String> = data.cells().iter().map(|(a, v)| format!("{}={}", a, v)).collect();
+                writeln!(w, "sparse_memory {} {}", data.len(), cells.join(","))?
+            }
+        }
+        w.flush()
+    }
+
+    pub fn load_snapshot<R: BufRead>(r: R) -> Result<Self, SnapshotError> {
+        let mut lines = r.lines().enumerate().map(|(i, line)| line.map(|l| (i + 1, l)));
+        let mut field = |keys: &[&str]| -> Result<(usize, String, String), SnapshotError> {
+            let (line, text) = lines.next()
+                .unwrap_or_else(|| Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated snapshot")))?;
+            let mut parts = text.splitn(2, ' ');
+            match parts.next() {
+                Some(key) if keys.contains(&key) =>
+                    Ok((line, key.to_string(), parts.next().unwrap_or("").trim().to_string())),
+                _ => Err(SnapshotError::Malformed { line, message: format!("expected {:?}", keys.join(" or ")) }),
+            }
+        };
+
+        let (line, _, version) = field(&[MAGIC])?;
+        let version = parse::<u32>(line, &version)?;
+        if version == 0 || version > VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let (line, _, idx) = field(&["idx"])?;
+        let idx = parse::<usize>(line, &idx)?;
+        let (line, _, relative_base) = field(&["relative_base"])?;
+        let relative_base = parse::<i64>(line, &relative_base)?;
+        let (line, _, halted) = field(&["halted"])?;
+        let halted = parse::<u8>(line, &halted)? != 0;
+        let arithmetic = if version < 3 {
+            Arithmetic::Checked
+        } else {
+            let (line, _, arithmetic) = field(&["arithmetic"])?;
+            parse_arithmetic(line, &arithmetic)?
+        };
+        let (line, _, input) = field(&["input"])?;
+        let input = parse_list(line, &input)?;
+
+        let memory_keys: &[&str] = if version == 1 { &["memory"] } else { &["memory", "sparse_memory"] };
+        let (line, key, memory) = field(memory_keys)?;
+        let data = match key.as_str() {
+            "memory" => Storage::Dense(parse_list(line, &memory)?),
+            _ => Storage::Sparse(parse_sparse(line, &memory)?),
+        };
+
+        let mut comp = IntCodeComputer::new(Vec::new(), input);
+        comp.data = data;
+        comp.idx = idx;
+        comp.relative_base = relative_base;
+        comp.halted = halted;
+        comp.arithmetic = arithmetic;
+        Ok(comp)
+    }
+
+    pub fn save_snapshot_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.save_snapshot(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn load_snapshot_file<P: AsRef<Path>>(path: P) -> Result<Self, SnapshotError> {
+        Self::load_snapshot(BufReader::new(File::open(path)?))
+    }
+}
+
+fn join<'a, I: IntoIterator<Item=&'a i64>>(values: I) -> String {
+    values.into_iter().map(i64::to_string).collect::<Vec<_>>().join(",")
+}
+
+fn arithmetic_name(arithmetic: Arithmetic) -> &'static str {
+    match arithmetic {
+        Arithmetic::Checked => "checked",
+        Arithmetic::Wrapping => "wrapping",
+        Arithmetic::Saturating => "saturating",
+    }
+}
+
+fn parse_arithmetic(line: usize, value: &str) -> Result<Arithmetic, SnapshotError> {
+    match value {
+        "checked" => Ok(Arithmetic::Checked),
+        "wrapping" => Ok(Arithmetic::Wrapping),
+        "saturating" => Ok(Arithmetic::Saturating),
+        _ => Err(SnapshotError::Malformed { line, message: format!("invalid value {:?}", value) }),
+    }
+}
+
+fn parse<T: std::str::FromStr>(line: usize, value: &str) -> Result<T, SnapshotError> {
+    value.parse::<T>()
+        .map_err(|_| SnapshotError::Malformed { line, message: format!("invalid value {:?}", value) })
+}
+
+fn parse_sparse(line: usize, value: &str) -> Result<SparseMemory, SnapshotError> {
+    let mut parts = value.splitn(2, ' ');
+    let len = parse::<usize>(line, parts.next().unwrap_or(""))?;
+    let mut memory = SparseMemory::with_len(len);
+
+    for cell in parts.next().unwrap_or("").split(',').filter(|c| !c.is_empty()) {
+        let mut kv = cell.splitn(2, '=');
+        let address = parse::<usize>(line, kv.next().unwrap_or(""))?;
+        let value = parse::<i64>(line, kv.next().unwrap_or(""))?;
+        *memory.get_mut(address) = value;
+    }
+    Ok(memory)
+}
+
+fn parse_list(line: usize, values: &str) -> Result<Vec<i64>, SnapshotError> {
+    if values.is_empty() {
+        return Ok(Vec::new());
+    }
+    values.split(',').map(|v| parse::<i64>(line, v)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::State;
+
+    fn round_trip(comp: &IntCodeComputer) -> IntCodeComputer {
+        let mut buf = Vec::new();
+        comp.save_snapshot(&mut buf).unwrap();
+        IntCodeComputer::load_snapshot(&buf[..]).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_format() {
+        let mut comp = IntCodeComputer::new(vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8], vec![8, 5]);
+        comp.try_step().unwrap();
+
+        let mut buf = Vec::new();
+        comp.save_snapshot(&mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "\
+intcode-snapshot 3
+idx 2
+relative_base 0
+halted 0
+arithmetic checked
+input 5
+memory 3,9,8,9,10,9,4,9,99,8,8
+");
+    }
+
+    #[test]
+    fn test_resume_from_snapshot() {
+        let mut comp = IntCodeComputer::new(vec![109, 3, 3, 9, 204, 6, 1105, 1, 2, 0], None);
+        assert_eq!(comp.resume_with(4), Ok(State::Output(4)));
+        assert_eq!(comp.resume(), Ok(State::NeedsInput));
+
+        let mut restored = round_trip(&comp);
+        assert_eq!(restored.relative_base, 3);
+        assert_eq!(restored.resume_with(5), Ok(State::Output(5)));
+        assert_eq!(comp.resume_with(5), Ok(State::Output(5)));
+    }
+
+    #[test]
+    fn test_snapshot_file() {
+        let mut comp = IntCodeComputer::new(vec![104, 1, 99], vec![1, 2]);
+        comp.run();
+
+        let path = std::env::temp_dir().join(format!("intcode-snapshot-{}.txt", std::process::id()));
+        comp.save_snapshot_file(&path).unwrap();
+        let restored = IntCodeComputer::load_snapshot_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(restored.is_halted());
+        assert_eq!(restored.idx, 2);
+        assert_eq!(restored.input, vec![1, 2]);
+        assert_eq!(restored.data, Storage::Dense(vec![104, 1, 99]));
+    }
+
+    #[test]
+    fn test_sparse_snapshot() {
+        let mut comp = IntCodeComputer::new_sparse(vec![1101, 2, 3, 5_000_000_000, 3, 0, 99], vec![]);
+        assert_eq!(comp.resume(), Ok(State::NeedsInput));
+
+        let mut buf = Vec::new();
+        comp.save_snapshot(&mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap().lines().last(),
+                   Some("sparse_memory 5000000001 0=1101,1=2,2=3,3=5000000000,4=3,6=99,5000000000=5"));
+
+        let mut restored = round_trip(&comp);
+        assert_eq!(restored.data, comp.data);
+        assert_eq!(restored.resume_with(4), Ok(State::Halted));
+        assert_eq!(restored[0], 4);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut comp = IntCodeComputer::new(vec![1101, i64::MAX, 1, 5, 99, 0], None);
+        comp.set_arithmetic(Arithmetic::Wrapping);
+
+        let mut restored = round_trip(&comp);
+        assert_eq!(restored.arithmetic, Arithmetic::Wrapping);
+        assert_eq!(restored.try_run(), Ok(vec![]));
+        assert_eq!(restored[5], i64::MIN);
+
+        let v2 = "intcode-snapshot 2\nidx 0\nrelative_base 0\nhalted 0\ninput\nmemory 99\n";
+        assert_eq!(IntCodeComputer::load_snapshot(v2.as_bytes()).unwrap().arithmetic, Arithmetic::Checked);
+    }
+
+    #[test]
+    fn test_version_1() {
+        let v1 = "intcode-snapshot 1\nidx 2\nrelative_base 0\nhalted 0\ninput 5\nmemory 3,9,104,7,99\n";
+        let mut comp = IntCodeComputer::load_snapshot(v1.as_bytes()).unwrap();
+        assert_eq!(comp.next(), Some(7));
+    }
+
+    #[test]
+    fn test_snapshot_errors() {
+        let load = |s: &str| match IntCodeComputer::load_snapshot(s.as_bytes()) {
+            Ok(_) => panic!("snapshot {:?} should not load", s),
+            Err(err) => err.to_string(),
+        };
+
+        assert_eq!(load("intcode-snapshot 4\n"), "unsupported snapshot version 4");
+        assert_eq!(load("intcode-snapshot 2\nidx x\n"), "malformed snapshot on line 2: invalid value \"x\"");
+        assert_eq!(load("intcode-snapshot 2\npc 0\n"), "malformed snapshot on line 2: expected \"idx\"");
+        assert_eq!(load("intcode-snapshot 2\nidx 0\n"), "snapshot io error: truncated snapshot");
+        assert_eq!(
+            load("intcode-snapshot 3\nidx 0\nrelative_base 0\nhalted 0\narithmetic exact\n"),
+            "malformed snapshot on line 5: invalid value \"exact\"",
+        );
+        assert_eq!(
+            load("intcode-snapshot 1\nidx 0\nrelative_base 0\nhalted 0\ninput\nsparse_memory 1 0=99\n"),
+            "malformed snapshot on line 6: expected \"memory\"",
+        );
+    }
+}
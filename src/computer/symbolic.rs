@@ -0,0 +1,544 @@
+use super::{IntCodeComputer, IntCodeError, Mode, Op};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+/// An unknown value: a memory cell's initial contents, or the nth input read.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug)]
+pub enum Var {
+    Cell(usize),
+    Input(usize),
+}
+
+impl fmt::Display for Var {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Var::Cell(address) => write!(f, "[{}]", address),
+            Var::Input(n) => write!(f, "in{}", n),
+        }
+    }
+}
+
+/// A value computed from symbolic variables. Constant subexpressions are folded as they are
+/// built, and `Load` stands for whatever memory held at an address that was itself symbolic.
+#[derive(PartialEq, Clone, Debug)]
+pub enum Expr {
+    Const(i64),
+    Var(Var),
+    Add(Rc<Expr>, Rc<Expr>),
+    Mul(Rc<Expr>, Rc<Expr>),
+    Less(Rc<Expr>, Rc<Expr>),
+    Equal(Rc<Expr>, Rc<Expr>),
+    Load(Rc<Expr>),
+}
+
+impl Expr {
+    pub fn constant(&self) -> Option<i64> {
+        match self {
+            Expr::Const(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn add(l: Rc<Expr>, r: Rc<Expr>) -> Rc<Expr> {
+        match (l.constant(), r.constant()) {
+            (Some(a), Some(b)) if a.checked_add(b).is_some() => Rc::new(Expr::Const(a + b)),
+            (Some(0), _) => r,
+            (_, Some(0)) => l,
+            _ => Rc::new(Expr::Add(l, r)),
+        }
+    }
+
+    fn mul(l: Rc<Expr>, r: Rc<Expr>) -> Rc<Expr> {
+        match (l.constant(), r.constant()) {
+            (Some(a), Some(b)) if a.checked_mul(b).is_some() => Rc::new(Expr::Const(a * b)),
+            (Some(0), _) | (_, Some(0)) => Rc::new(Expr::Const(0)),
+            (Some(1), _) => r,
+            (_, Some(1)) => l,
+            _ => Rc::new(Expr::Mul(l, r)),
+        }
+    }
+
+    fn less(l: Rc<Expr>, r: Rc<Expr>) -> Rc<Expr> {
+        match (l.constant(), r.constant()) {
+            (Some(a), Some(b)) => Rc::new(Expr::Const((a < b) as i64)),
+            _ => Rc::new(Expr::Less(l, r)),
+        }
+    }
+
+    fn equal(l: Rc<Expr>, r: Rc<Expr>) -> Rc<Expr> {
+        match (l.constant(), r.constant()) {
+            (Some(a), Some(b)) => Rc::new(Expr::Const((a == b) as i64)),
+            _ if l == r && !l.reads_memory() => Rc::new(Expr::Const(1)),
+            _ => Rc::new(Expr::Equal(l, r)),
+        }
+    }
+
+    /// Reports whether the expression depends on a `Load`. Such expressions are not pure: the
+    /// same load can see different values before and after a write.
+    pub fn reads_memory(&self) -> bool {
+        match self {
+            Expr::Const(_) | Expr::Var(_) => false,
+            Expr::Add(l, r) | Expr::Mul(l, r) | Expr::Less(l, r) | Expr::Equal(l, r) =>
+                l.reads_memory() || r.reads_memory(),
+            Expr::Load(_) => true,
+        }
+    }
+
+    /// Rewrites the expression as a sum of weighted variables, if it is one.
+    pub fn linear(&self) -> Option<Linear> {
+        match self {
+            Expr::Const(value) => Some(Linear { constant: *value, terms: BTreeMap::new() }),
+            Expr::Var(var) => Some(Linear { constant: 0, terms: vec![(*var, 1)].into_iter().collect() }),
+            Expr::Add(l, r) => l.linear()?.plus(&r.linear()?),
+            Expr::Mul(l, r) => {
+                let (l, r) = (l.linear()?, r.linear()?);
+                match (l.terms.is_empty(), r.terms.is_empty()) {
+                    (true, _) => r.times(l.constant),
+                    (_, true) => l.times(r.constant),
+                    _ => None,
+                }
+            }
+            Expr::Less(_, _) | Expr::Equal(_, _) | Expr::Load(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Const(value) => write!(f, "{}", value),
+            Expr::Var(var) => write!(f, "{}", var),
+            Expr::Add(l, r) => write!(f, "({} + {})", l, r),
+            Expr::Mul(l, r) => write!(f, "({} * {})", l, r),
+            Expr::Less(l, r) => write!(f, "({} < {})", l, r),
+            Expr::Equal(l, r) => write!(f, "({} == {})", l, r),
+            Expr::Load(address) => write!(f, "mem[{}]", address),
+        }
+    }
+}
+
+/// `constant + sum(coefficient * variable)`, with no zero coefficients.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Linear {
+    pub constant: i64,
+    pub terms: BTreeMap<Var, i64>,
+}
+
+impl Linear {
+    fn plus(mut self, other: &Linear) -> Option<Linear> {
+        self.constant = self.constant.checked_add(other.constant)?;
+        for (&var, &coefficient) in &other.terms {
+            let sum = self.terms.get(&var).unwrap_or(&0).checked_add(coefficient)?;
+            if sum == 0 {
+                self.terms.remove(&var);
+            } else {
+                self.terms.insert(var, sum);
+            }
+        }
+        Some(self)
+    }
+
+    fn times(mut self, factor: i64) -> Option<Linear> {
+        if factor == 0 {
+            return Some(Linear { constant: 0, terms: BTreeMap::new() });
+        }
+        self.constant = self.constant.checked_mul(factor)?;
+        for coefficient in self.terms.values_mut() {
+            *coefficient = coefficient.checked_mul(factor)?;
+        }
+        Some(self)
+    }
+
+    /// Finds values for the variables that make the expression equal `target`, keeping each
+    /// variable inside its range. At most one variable may be left unbounded; it is solved for
+    /// directly, otherwise the last bounded one is. The other variables are searched in order,
+    /// so the first solution found has the smallest of them.
+    pub fn solve(&self, target: i64, ranges: &BTreeMap<Var, RangeInclusive<i64>>) -> Option<BTreeMap<Var, i64>> {
+        if self.terms.is_empty() {
+            return if self.constant == target { Some(BTreeMap::new()) } else { None };
+        }
+
+        let mut unbounded = self.terms.keys().filter(|var| !ranges.contains_key(var));
+        let solved = match (unbounded.next(), unbounded.next()) {
+            (Some(&var), None) => var,
+            (None, None) => *self.terms.keys().next_back().expect("terms is not empty"),
+            _ => return None,
+        };
+
+        let searched: Vec<(Var, i64, RangeInclusive<i64>)> = self.terms.iter()
+            .filter(|(&var, _)| var != solved)
+            .map(|(&var, &coefficient)| (var, coefficient, ranges[&var].clone()))
+            .collect();
+        let mut values = BTreeMap::new();
+        let remaining = target.checked_sub(self.constant)?;
+
+        if self.search(solved, &searched, remaining, ranges.get(&solved), &mut values) {
+            Some(values)
+        } else {
+            None
+        }
+    }
+
+    fn search(
+        &self,
+        solved: Var,
+        searched: &[(Var, i64, RangeInclusive<i64>)],
+        remaining: i64,
+        range: Option<&RangeInclusive<i64>>,
+        values: &mut BTreeMap<Var, i64>,
+    ) -> bool {
+        let (var, coefficient, candidates) = match searched.first() {
+            Some(first) => first,
+            None => {
+                let coefficient = self.terms[&solved];
+                if remaining.checked_rem(coefficient) != Some(0) {
+                    return false;
+                }
+                let value = match remaining.checked_div(coefficient) {
+                    Some(value) => value,
+                    None => return false,
+                };
+                if range.is_some_and(|r| !r.contains(&value)) {
+                    return false;
+                }
+                values.insert(solved, value);
+                return true;
+            }
+        };
+
+        for value in candidates.clone() {
+            let rest = match coefficient.checked_mul(value).and_then(|term| remaining.checked_sub(term)) {
+                Some(rest) => rest,
+                None => continue,
+            };
+            values.insert(*var, value);
+            if self.search(solved, &searched[1..], rest, range, values) {
+                return true;
+            }
+        }
+        values.remove(var);
+        false
+    }
+}
+
+/// A failure while executing symbolically.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum SymbolicError {
+    /// A fault the concrete computer would also have raised.
+    Fault(IntCodeError),
+    SymbolicCode { idx: usize },
+    SymbolicAddress { idx: usize },
+    SymbolicBranch { idx: usize },
+}
+
+impl fmt::Display for SymbolicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolicError::Fault(err) => write!(f, "{}", err),
+            SymbolicError::SymbolicCode { idx } => write!(f, "symbolic instruction at idx {}", idx),
+            SymbolicError::SymbolicAddress { idx } => write!(f, "write or base adjustment through a symbolic address at idx {}", idx),
+            SymbolicError::SymbolicBranch { idx } => write!(f, "jump depends on a symbolic value at idx {}", idx),
+        }
+    }
+}
+
+impl Error for SymbolicError {}
+
+impl From<IntCodeError> for SymbolicError {
+    fn from(err: IntCodeError) -> Self { SymbolicError::Fault(err) }
+}
+
+/// Runs a program over expressions instead of numbers. Memory cells and inputs can be made
+/// symbolic, and `Add`, `Multiply`, `LessThan` and `Equals` build expressions from them. Only
+/// a single path is followed, so instructions, write addresses and jump conditions must stay
+/// concrete.
+pub struct SymbolicComputer {
+    idx: usize,
+    relative_base: i64,
+    halted: bool,
+    len: usize,
+    memory: HashMap<usize, Rc<Expr>>,
+    input: VecDeque<Rc<Expr>>,
+    inputs: usize,
+    outputs: Vec<Rc<Expr>>,
+}
+
+impl SymbolicComputer {
+    pub fn make_symbolic(&mut self, address: usize) -> Var {
+        let var = Var::Cell(address);
+        self.store(address, Rc::new(Expr::Var(var)));
+        var
+    }
+
+    pub fn push_input(&mut self, value: i64) { self.input.push_back(Rc::new(Expr::Const(value))); }
+
+    /// Queues an input whose value is unknown, returning the variable standing for it.
+    pub fn push_symbolic_input(&mut self) -> Var {
+        let var = Var::Input(self.inputs + self.input.len());
+        self.input.push_back(Rc::new(Expr::Var(var)));
+        var
+    }
+
+    pub fn cell(&self, address: usize) -> Expr { (*self.load(address)).clone() }
+
+    pub fn outputs(&self) -> Vec<Expr> { self.outputs.iter().map(|e| (**e).clone()).collect() }
+
+    pub fn is_halted(&self) -> bool { self.halted }
+
+    pub fn run(&mut self) -> Result<(), SymbolicError> {
+        while !self.halted {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    pub fn step(&mut self) -> Result<(), SymbolicError> {
+        use Op::*;
+
+        if self.halted {
+            return Ok(());
+        }
+        if self.idx >= self.len {
+            return Err(IntCodeError::OutOfBounds { idx: self.idx }.into());
+        }
+
+        let code = self.word(self.idx).ok_or(SymbolicError::SymbolicCode { idx: self.idx })?;
+        let op = Op::decode(code, self.idx)?;
+
+        match op {
+            Add(l, r, t) => self.binary(l, r, t, Expr::add)?,
+            Multiply(l, r, t) => self.binary(l, r, t, Expr::mul)?,
+            LessThan(l, r, t) => self.binary(l, r, t, Expr::less)?,
+            Equals(l, r, t) => self.binary(l, r, t, Expr::equal)?,
+            Input(t) => {
+                let target = self.target(1, t)?;
+                let value = self.input.pop_front()
+                    .ok_or(IntCodeError::InputExhausted { idx: self.idx })?;
+                self.inputs += 1;
+                self.store(target, value);
+            }
+            Output(mode) => {
+                let value = self.param(1, mode)?;
+                self.outputs.push(value);
+            }
+            JumpIfTrue(l, r) | JumpIfFalse(l, r) => {
+                let branch = SymbolicError::SymbolicBranch { idx: self.idx };
+                let cond = self.param(1, l)?;
+                let cond = cond.constant().filter(|_| !cond.reads_memory()).ok_or(branch)?;
+                if (cond != 0) == matches!(op, JumpIfTrue(_, _)) {
+                    let target = self.param(2, r)?.constant().ok_or(branch)?;
+                    self.idx = self.address(target)?;
+                    return Ok(());
+                }
+            }
+            AdjustRelativeBase(mode) => {
+                let offset = self.param(1, mode)?.constant()
+                    .ok_or(SymbolicError::SymbolicAddress { idx: self.idx })?;
+                self.relative_base = self.relative_base.checked_add(offset)
+                    .ok_or(IntCodeError::Overflow { idx: self.idx })?;
+            }
+            Halt => {
+                self.halted = true;
+                return Ok(());
+            }
+        }
+
+        self.idx += op.len();
+        Ok(())
+    }
+
+    fn binary(&mut self, l: Mode, r: Mode, t: Mode, f: fn(Rc<Expr>, Rc<Expr>) -> Rc<Expr>) -> Result<(), SymbolicError> {
+        let value = f(self.param(1, l)?, self.param(2, r)?);
+        let target = self.target(3, t)?;
+        self.store(target, value);
+        Ok(())
+    }
+
+    fn param(&self, offset: usize, mode: Mode) -> Result<Rc<Expr>, SymbolicError> {
+        let value = self.load(self.idx + offset);
+        let address = match (mode, value.constant()) {
+            (Mode::Immediate, _) => return Ok(value),
+            (Mode::Position, Some(address)) => address,
+            (Mode::Relative, Some(offset)) => self.relative_base.checked_add(offset)
+                .ok_or(IntCodeError::Overflow { idx: self.idx })?,
+            (Mode::Position, None) => return Ok(Rc::new(Expr::Load(value))),
+            (Mode::Relative, None) =>
+                return Ok(Rc::new(Expr::Load(Expr::add(Rc::new(Expr::Const(self.relative_base)), value)))),
+        };
+        Ok(self.load(self.address(address)?))
+    }
+
+    fn target(&self, offset: usize, mode: Mode) -> Result<usize, SymbolicError> {
+        let value = self.word(self.idx + offset).ok_or(SymbolicError::SymbolicAddress { idx: self.idx })?;
+        let address = match mode {
+            Mode::Position => value,
+            Mode::Relative => self.relative_base.checked_add(value)
+                .ok_or(IntCodeError::Overflow { idx: self.idx })?,
+            Mode::Immediate => return Err(IntCodeError::ImmediateWrite { idx: self.idx }.into()),
+        };
+        Ok(self.address(address)?)
+    }
+
+    fn address(&self, value: i64) -> Result<usize, IntCodeError> {
+        if value < 0 {
+            return Err(IntCodeError::NegativeAddress { idx: self.idx, address: value });
+        }
+        Ok(value as usize)
+    }
+
+    fn word(&self, address: usize) -> Option<i64> { self.load(address).constant() }
+
+    fn load(&self, address: usize) -> Rc<Expr> {
+        self.memory.get(&address).cloned().unwrap_or_else(|| Rc::new(Expr::Const(0)))
+    }
+
+    fn store(&mut self, address: usize, value: Rc<Expr>) {
+        self.len = self.len.max(address + 1);
+        self.memory.insert(address, value);
+    }
+}
+
+impl IntCodeComputer {
+    /// Copies the computer's memory, registers and queued input into a symbolic computer.
+    pub fn symbolic(&self) -> SymbolicComputer {
+        SymbolicComputer {
+            idx: self.idx,
+            relative_base: self.relative_base,
+            halted: self.halted,
+            len: self.len(),
+            memory: self.data.addresses()
+                .filter(|&a| self.read(a) != 0)
+                .map(|a| (a, Rc::new(Expr::Const(self.read(a)))))
+                .collect(),
+            input: self.input.iter().map(|&v| Rc::new(Expr::Const(v))).collect(),
+            inputs: 0,
+            outputs: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::assembler::assemble;
+
+    #[test]
+    fn test_expressions() {
+        // [x] = ([x] + 3) * in0, then outputs ([x] < 10) and (in1 == in1).
+        let program = assemble("
+                    ADD [x] #3 -> [x]
+                    IN -> [t]
+                    MUL [x] [t] -> [x]
+                    LT [x] #10 -> [t]
+                    OUT [t]
+                    IN -> [t]
+                    EQ [t] [t] -> [t]
+                    OUT [t]
+                    HLT
+            x:      .data 0
+            t:      .data 0
+        ").unwrap();
+        let comp = IntCodeComputer::new(program, None);
+        let x = 25;
+
+        let mut sym = comp.symbolic();
+        assert_eq!(sym.make_symbolic(x), Var::Cell(x));
+        assert_eq!(sym.push_symbolic_input(), Var::Input(0));
+        sym.push_input(4);
+        sym.run().unwrap();
+
+        assert!(sym.is_halted());
+        assert_eq!(sym.cell(x).to_string(), "(([25] + 3) * in0)");
+        assert_eq!(sym.outputs().iter().map(Expr::to_string).collect::<Vec<_>>(),
+                   vec!["((([25] + 3) * in0) < 10)", "1"]);
+        assert_eq!(sym.cell(x).linear(), None);
+
+        let mut concrete = comp.symbolic();
+        concrete.push_input(5);
+        concrete.push_input(0);
+        concrete.run().unwrap();
+        assert_eq!(concrete.cell(x), Expr::Const(15));
+    }
+
+    #[test]
+    fn test_linear_solve() {
+        let (a, b) = (Var::Cell(1), Var::Cell(2));
+        let expr = Expr::add(
+            Expr::mul(Rc::new(Expr::Var(a)), Rc::new(Expr::Const(6))),
+            Expr::add(Rc::new(Expr::Var(b)), Rc::new(Expr::Const(-4))),
+        );
+        let linear = expr.linear().unwrap();
+        assert_eq!(linear.constant, -4);
+        assert_eq!(linear.terms, vec![(a, 6), (b, 1)].into_iter().collect());
+
+        let ranges = vec![(a, 0..=9), (b, 0..=5)].into_iter().collect();
+        assert_eq!(linear.solve(38, &ranges), Some(vec![(a, 7), (b, 0)].into_iter().collect()));
+        assert_eq!(linear.solve(100, &ranges), None);
+
+        let negated = Expr::mul(Rc::new(Expr::Var(a)), Rc::new(Expr::Const(-1))).linear().unwrap();
+        assert_eq!(negated.solve(i64::MIN, &BTreeMap::new()), None);
+        assert_eq!(negated.solve(i64::MAX, &BTreeMap::new()), Some(vec![(a, -i64::MAX)].into_iter().collect()));
+
+        let constant = Expr::add(Rc::new(Expr::Const(2)), Rc::new(Expr::Const(3))).linear().unwrap();
+        assert_eq!(constant.solve(5, &BTreeMap::new()), Some(BTreeMap::new()));
+        assert_eq!(constant.solve(6, &BTreeMap::new()), None);
+
+        let single: BTreeMap<_, _> = vec![(b, 3..=3)].into_iter().collect();
+        assert_eq!(linear.solve(17, &single), Some(vec![(a, 3), (b, 3)].into_iter().collect()));
+        assert_eq!(linear.solve(18, &single), None);
+        assert_eq!(linear.solve(17, &BTreeMap::new()), None);
+    }
+
+    #[test]
+    fn test_sparse() {
+        let mut comp = IntCodeComputer::new_sparse(vec![1, 5_000_000_000, 5, 0, 99, 3], None);
+        comp[5_000_000_000] = 4;
+
+        let mut sym = comp.symbolic();
+        let var = sym.make_symbolic(5);
+        sym.run().unwrap();
+        assert_eq!(sym.cell(0), Expr::Add(Rc::new(Expr::Const(4)), Rc::new(Expr::Var(var))));
+    }
+
+    #[test]
+    fn test_symbolic_errors() {
+        let mut branch = IntCodeComputer::new(vec![1005, 5, 0, 99, 0, 0], None).symbolic();
+        branch.make_symbolic(5);
+        assert_eq!(branch.run(), Err(SymbolicError::SymbolicBranch { idx: 0 }));
+
+        let mut write = IntCodeComputer::new(vec![1101, 1, 2, 9, 99], None).symbolic();
+        write.make_symbolic(3);
+        assert_eq!(write.run(), Err(SymbolicError::SymbolicAddress { idx: 0 }));
+
+        let mut code = IntCodeComputer::new(vec![99], None).symbolic();
+        code.make_symbolic(0);
+        assert_eq!(code.run(), Err(SymbolicError::SymbolicCode { idx: 0 }));
+
+        // Copies a symbolic pointer into two loads with a write between them, then branches on
+        // whether they matched; the loads can differ, so the branch is undecidable.
+        let program = assemble("
+                    ADD [p] #0 -> [i1+1]
+                    ADD [p] #0 -> [i2+1]
+            i1:     ADD [0] #0 -> [a]
+                    ADD #7 #0 -> [t]
+            i2:     ADD [0] #0 -> [b]
+                    EQ [a] [b] -> [t]
+                    JT [t] #done
+            done:   HLT
+            p:      .data 0
+            a:      .data 0
+            b:      .data 0
+            t:      .data 0
+        ").unwrap();
+        let mut loads = IntCodeComputer::new(program, None).symbolic();
+        loads.make_symbolic(28);
+        assert_eq!(loads.run(), Err(SymbolicError::SymbolicBranch { idx: 24 }));
+        assert!(loads.cell(29).reads_memory());
+
+        let mut input = IntCodeComputer::new(vec![3, 0, 99], None).symbolic();
+        assert_eq!(input.run(), Err(SymbolicError::Fault(IntCodeError::InputExhausted { idx: 0 })));
+    }
+}
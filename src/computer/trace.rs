@@ -0,0 +1,210 @@
+use super::{IntCodeComputer, Mode, Op};
+use std::fmt;
+use std::io::{self, Write};
+
+/// A single executed instruction. `params` holds each operand's resolved value, except for a
+/// write target, which is recorded as the address written to.
+#[derive(PartialEq, Clone, Debug)]
+pub struct TraceEntry {
+    pub idx: usize,
+    pub op: Op,
+    pub params: Vec<i64>,
+    pub write: Option<TraceWrite>,
+    pub input: Option<i64>,
+    pub output: Option<i64>,
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct TraceWrite {
+    pub address: usize,
+    pub old: i64,
+    pub new: i64,
+}
+
+impl TraceEntry {
+    pub fn to_json(&self) -> String {
+        let modes: Vec<String> = self.op.modes().iter()
+            .map(|mode| format!("{:?}", mode.name()))
+            .collect();
+        let params: Vec<String> = self.params.iter().map(i64::to_string).collect();
+        let write = self.write.map_or("null".to_string(), |w| {
+            format!(r#"{{"address":{},"old":{},"new":{}}}"#, w.address, w.old, w.new)
+        });
+        let opt = |v: Option<i64>| v.map_or("null".to_string(), |v| v.to_string());
+
+        format!(
+            r#"{{"idx":{},"op":"{}","modes":[{}],"params":[{}],"write":{},"input":{},"output":{}}}"#,
+            self.idx, self.op.mnemonic(), modes.join(","), params.join(","), write,
+            opt(self.input), opt(self.output),
+        )
+    }
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.idx, self.op.mnemonic())?;
+        for param in &self.params {
+            write!(f, " {}", param)?;
+        }
+        if let Some(w) = self.write {
+            write!(f, " | [{}] {} -> {}", w.address, w.old, w.new)?;
+        }
+        if let Some(input) = self.input {
+            write!(f, " | in {}", input)?;
+        }
+        if let Some(output) = self.output {
+            write!(f, " | out {}", output)?;
+        }
+        Ok(())
+    }
+}
+
+/// The recorded execution history of a computer, exportable as plain text or JSON Lines.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Trace(pub Vec<TraceEntry>);
+
+impl Trace {
+    pub fn write_text<W: Write>(&self, mut w: W) -> io::Result<()> {
+        self.0.iter().try_for_each(|entry| writeln!(w, "{}", entry))
+    }
+
+    pub fn write_json_lines<W: Write>(&self, mut w: W) -> io::Result<()> {
+        self.0.iter().try_for_each(|entry| writeln!(w, "{}", entry.to_json()))
+    }
+
+    pub fn to_text(&self) -> String { self.0.iter().map(|e| format!("{}\n", e)).collect() }
+
+    pub fn to_json_lines(&self) -> String { self.0.iter().map(|e| format!("{}\n", e.to_json())).collect() }
+}
+
+impl IntCodeComputer {
+    /// Starts recording every instruction executed from here on.
+    pub fn enable_trace(&mut self) {
+        if self.trace.is_none() {
+            self.trace = Some(Vec::new());
+        }
+    }
+
+    /// Stops recording and returns everything recorded so far.
+    pub fn disable_trace(&mut self) -> Trace { Trace(self.trace.take().unwrap_or_default()) }
+
+    /// Returns the entries recorded so far, leaving tracing enabled.
+    pub fn take_trace(&mut self) -> Trace {
+        Trace(self.trace.as_mut().map(std::mem::take).unwrap_or_default())
+    }
+
+    pub(super) fn begin_trace(&self) -> Option<TraceEntry> {
+        let op = Op::decode(self.read(self.idx), self.idx).ok()?;
+        let modes = op.modes();
+
+        let params = modes.iter().enumerate()
+            .map(|(i, &mode)| {
+                if op.writes() && i == modes.len() - 1 {
+                    self.target(i + 1, mode).map(|address| address as i64)
+                } else {
+                    self.param(i + 1, mode)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?;
+
+        let write = self.write_target()
+            .map(|address| TraceWrite { address, old: self.read(address), new: 0 });
+        let input = match op {
+            Op::Input(_) => self.input.front().copied(),
+            _ => None,
+        };
+
+        Some(TraceEntry { idx: self.idx, op, params, write, input, output: None })
+    }
+
+    pub(super) fn end_trace(&mut self, mut entry: TraceEntry, output: Option<i64>) {
+        if let Some(w) = entry.write.as_mut() {
+            w.new = self.read(w.address);
+        }
+        entry.output = output;
+
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(entry);
+        }
+    }
+}
+
+impl Mode {
+    fn name(self) -> &'static str {
+        match self {
+            Mode::Position => "position",
+            Mode::Immediate => "immediate",
+            Mode::Relative => "relative",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace() {
+        let mut comp = IntCodeComputer::new(vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8], vec![8]);
+        comp.enable_trace();
+        comp.run();
+
+        let trace = comp.disable_trace();
+        assert_eq!(trace.0.len(), 4);
+        assert_eq!(trace.0[1], TraceEntry {
+            idx: 2,
+            op: Op::Equals(Mode::Position, Mode::Position, Mode::Position),
+            params: vec![8, 8, 9],
+            write: Some(TraceWrite { address: 9, old: 8, new: 1 }),
+            input: None,
+            output: None,
+        });
+
+        assert_eq!(trace.to_text(), "\
+0 IN 9 | [9] -1 -> 8 | in 8
+2 EQ 8 8 9 | [9] 8 -> 1
+6 OUT 1 | out 1
+8 HLT
+");
+
+        let mut json = Vec::new();
+        trace.write_json_lines(&mut json).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert_eq!(json.lines().next(), Some(
+            r#"{"idx":0,"op":"IN","modes":["position"],"params":[9],"write":{"address":9,"old":-1,"new":8},"input":8,"output":null}"#
+        ));
+        assert_eq!(json.lines().nth(3), Some(
+            r#"{"idx":8,"op":"HLT","modes":[],"params":[],"write":null,"input":null,"output":null}"#
+        ));
+    }
+
+    #[test]
+    fn test_trace_toggling() {
+        let mut comp = IntCodeComputer::new(vec![1101, 1, 2, 0, 104, 5, 99], None);
+        assert_eq!(comp.try_step(), Ok(None));
+        comp.enable_trace();
+        assert_eq!(comp.try_step(), Ok(Some(5)));
+        assert_eq!(comp.take_trace().to_text(), "4 OUT 5 | out 5\n");
+        comp.run();
+        assert_eq!(comp.disable_trace().to_text(), "6 HLT\n");
+        assert_eq!(comp.disable_trace(), Trace::default());
+    }
+
+    #[test]
+    fn test_diagnostic_traces() {
+        let trace = |input| {
+            let mut comp = IntCodeComputer::from_input_file("dec05.txt", vec![input]);
+            comp.enable_trace();
+            comp.run();
+            comp.disable_trace()
+        };
+
+        let (first, second) = (trace(1), trace(5));
+        assert_eq!(first.0[0].input, Some(1));
+        assert_eq!(second.0[0].input, Some(5));
+        assert_eq!(first.0.last().map(|e| e.op), Some(Op::Halt));
+        assert_eq!(first.0.iter().rev().find_map(|e| e.output), Some(9_219_874));
+        assert_eq!(second.0.iter().rev().find_map(|e| e.output), Some(5_893_654));
+    }
+}
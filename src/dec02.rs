@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
     use crate::computer::IntCodeComputer;
+    use crate::computer::search::{search, Candidate};
 
     fn run_noun_verb(comp: &IntCodeComputer, noun: i64, verb: i64) -> i64 {
         let mut comp = comp.clone();
@@ -12,24 +13,34 @@ mod tests {
 
     #[test]
     fn part1() {
-        let comp = IntCodeComputer::from_input_file("dec02.txt", 0);
+        let comp = IntCodeComputer::from_input_file("dec02.txt", None);
         assert_eq!(run_noun_verb(&comp, 12, 2), 7_594_646);
     }
 
     #[test]
     fn part2() {
         const TARGET: i64 = 19_690_720;
-        let comp = IntCodeComputer::from_input_file("dec02.txt", 0);
+        let comp = IntCodeComputer::from_input_file("dec02.txt", None);
 
-        for noun in 0..100 {
-            for verb in 0..100 {
-                if run_noun_verb(&comp, noun, verb) == TARGET {
-                    assert_eq!(100 * noun + verb, 3376);
-                    return;
-                }
-            }
-        }
+        let candidates = (0..100)
+            .flat_map(|noun| (0..100).map(move |verb| Candidate::patches(vec![(1, noun), (2, verb)])));
+        let found = search(&comp, candidates, |comp, _| comp[0] == TARGET)
+            .expect("should have found a valid noun and verb");
 
-        panic!("should have found a valid noun and verb");
+        let (noun, verb) = (found.computer[1], found.computer[2]);
+        assert_eq!(100 * noun + verb, 3376);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn part2_symbolic() {
+        let comp = IntCodeComputer::from_input_file("dec02.txt", None);
+        let mut sym = comp.symbolic();
+        let (noun, verb) = (sym.make_symbolic(1), sym.make_symbolic(2));
+        sym.run().unwrap();
+
+        let result = sym.cell(0).linear().expect("result should be linear in noun and verb");
+        let ranges = vec![(noun, 0..=99), (verb, 0..=99)].into_iter().collect();
+        let solution = result.solve(19_690_720, &ranges).expect("should have found a valid noun and verb");
+        assert_eq!(100 * solution[&noun] + solution[&verb], 3376);
+    }
+}
@@ -1,8 +1,6 @@
 use crate::read_input;
 use std::io::BufRead;
 use std::cmp::{min, max};
-use std::i64;
-use std::usize;
 use std::ops::Add;
 use std::iter::FromIterator;
 
@@ -56,7 +54,7 @@ struct Point {
 impl Point {
     fn new(x: i64, y: i64) -> Self { Self { x, y } }
 
-    fn steps_to(&self, pt: Self) -> i64 { ((self.x - pt.x).abs() + (self.y - pt.y).abs()) }
+    fn steps_to(&self, pt: Self) -> i64 { (self.x - pt.x).abs() + (self.y - pt.y).abs() }
 }
 
 impl Add<Path> for Point {
@@ -126,8 +124,7 @@ impl Wire {
             self.into_iter()
                 .map(move |segment| segment.intersects(other_segment))
         })
-            .filter(Option::is_some)
-            .map(Option::unwrap)
+            .flatten()
             .collect()
     }
 
@@ -202,7 +199,7 @@ impl<'a> IntoIterator for &'a Wire {
     type Item = <&'a Vec<Segment> as IntoIterator>::Item;
     type IntoIter = <&'a Vec<Segment> as IntoIterator>::IntoIter;
 
-    fn into_iter(self) -> Self::IntoIter { (&self.0).iter() }
+    fn into_iter(self) -> Self::IntoIter { self.0.iter() }
 }
 
 fn input_to_wires(name: &str) -> Vec<Wire> {
@@ -24,7 +24,7 @@ impl<'a> Space<'a> {
     fn orbits(&self, name: &str) -> usize {
         match self.nodes.get(name) {
             None => 0,
-            Some(o) => 1 + self.orbits(&o.orbits),
+            Some(o) => 1 + self.orbits(o.orbits),
         }
     }
 